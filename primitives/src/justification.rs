@@ -101,3 +101,52 @@ pub fn verify_justification(justification: &CircuitJustification) {
         "More than 2/3 of signatures are not verifie!"
     );
 }
+
+/// Verify a justification like [`verify_justification`], returning an error instead of panicking if
+/// it is invalid. Used outside of the program, to check justifications without unwinding.
+pub fn try_verify_justification(justification: &CircuitJustification) -> Result<(), String> {
+    let mut ancestry_map: HashMap<B256, B256> = HashMap::new();
+    for encoded_header in &justification.ancestries_encoded {
+        let parent_hash: [u8; 32] = encoded_header
+            .get(0..32)
+            .and_then(|parent_hash| parent_hash.try_into().ok())
+            .ok_or_else(|| "Ancestry header is too short.".to_string())?;
+        ancestry_map.insert(hash_encoded_header(encoded_header), B256::from(parent_hash));
+    }
+
+    let mut signer_addresses: HashSet<B256> = HashSet::new();
+    for p in &justification.precommits {
+        let signed_message = Encode::encode(&(
+            1u8,
+            p.target_hash.0,
+            p.target_number,
+            &justification.round,
+            &justification.authority_set_id,
+        ));
+
+        let pubkey = VerificationKey::try_from(p.pubkey.0)
+            .map_err(|_| format!("Invalid Ed25519 public key {}.", p.pubkey))?;
+        pubkey
+            .verify(&Signature::from(p.signature.0), &signed_message)
+            .map_err(|_| format!("Failed to verify Ed25519 signature of {}.", p.pubkey))?;
+
+        if confirm_ancestry(&p.target_hash, &justification.block_hash, &ancestry_map) {
+            signer_addresses.insert(p.pubkey);
+        }
+    }
+
+    let num_matched_addresses = signer_addresses
+        .iter()
+        .filter(|x| justification.valset_pubkeys.iter().any(|e| e.0.eq(&x[..])))
+        .count();
+
+    if !is_signed_by_supermajority(num_matched_addresses, justification.valset_pubkeys.len()) {
+        return Err(format!(
+            "Only {} of {} authorities signed the justification, which is not a supermajority.",
+            num_matched_addresses,
+            justification.valset_pubkeys.len()
+        ));
+    }
+
+    Ok(())
+}
@@ -13,7 +13,7 @@ pub mod merkle;
 pub mod rotate;
 pub mod types;
 
-pub use justification::verify_justification;
+pub use justification::{try_verify_justification, verify_justification};
 
 /// Blake2B hash of an encoded header. Note: This is a generic hash fn for any data.
 pub(crate) fn hash_encoded_header(encoded_header: &[u8]) -> B256 {
@@ -66,3 +66,67 @@ pub fn get_merkle_root_commitments(
 
     (state_root_commitment, data_root_commitment)
 }
+
+/// Computes the Merkle branch of the leaf at `index`, for the tree built by [`get_merkle_root`]
+/// after padding the leaves to `tree_size`. The branch is ordered from the leaf's sibling up to the
+/// child of the root.
+pub fn get_merkle_branch(leaves: &[B256], tree_size: usize, index: usize) -> Vec<B256> {
+    assert!(tree_size.is_power_of_two());
+    assert!(tree_size >= leaves.len());
+    assert!(index < tree_size);
+
+    let mut nodes = leaves.to_vec();
+    nodes.resize(tree_size, B256::from([0u8; 32]));
+
+    let mut branch = Vec::new();
+    let mut index = index;
+    while nodes.len() > 1 {
+        branch.push(nodes[index ^ 1]);
+        nodes = (0..nodes.len() / 2)
+            .map(|i| {
+                let mut hasher = Sha256::new();
+                hasher.update(nodes[2 * i]);
+                hasher.update(nodes[2 * i + 1]);
+                B256::from_slice(&hasher.finalize())
+            })
+            .collect();
+        index /= 2;
+    }
+
+    branch
+}
+
+/// Computes the root of the tree from a leaf at `index` and its Merkle branch.
+pub fn get_merkle_root_from_branch(leaf: B256, branch: &[B256], index: usize) -> B256 {
+    let mut node = leaf;
+    for (height, sibling) in branch.iter().enumerate() {
+        let mut hasher = Sha256::new();
+        if (index >> height) & 1 == 0 {
+            hasher.update(node);
+            hasher.update(sibling);
+        } else {
+            hasher.update(sibling);
+            hasher.update(node);
+        }
+        node = B256::from_slice(&hasher.finalize());
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_branch() {
+        let leaves: Vec<B256> = (1..=5u8).map(|i| B256::from([i; 32])).collect();
+        let root = get_merkle_root(leaves.clone());
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let branch = get_merkle_branch(&leaves, 8, index);
+            assert_eq!(branch.len(), 3);
+            assert_eq!(get_merkle_root_from_branch(*leaf, &branch, index), root);
+        }
+    }
+}
@@ -94,7 +94,7 @@ pub struct CircuitJustification {
     pub ancestries_encoded: Vec<Vec<u8>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct HeaderRangeInputs {
     pub merkle_tree_size: usize,
     pub encoded_headers: Vec<Vec<u8>>,
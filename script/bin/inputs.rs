@@ -0,0 +1,138 @@
+//! Export the inputs to an SP1 Vector proof to a file, and execute the program from that file offline.
+//!
+//! To export the inputs for a header range proof:
+//!
+//!     `cargo run --bin inputs -- export-header-range --trusted-block <BLOCK> --target-block <BLOCK> --out inputs.bin`
+//!
+//! To execute the program from the exported inputs:
+//!
+//!     `cargo run --bin inputs -- execute --file inputs.bin`
+//!
+use std::path::PathBuf;
+
+use alloy::sol_types::SolType;
+use clap::{Parser, Subcommand};
+use services::input::{HeaderRangeRequestData, RpcDataFetcher};
+use services::verify::{check_header_range_inputs, check_rotate_inputs};
+use sp1_sdk::{utils::setup_logger, ProverClient};
+use sp1_vector_primitives::types::ProofOutput;
+use sp1_vectorx_script::inputs::{ProofInputs, ProofInputsFile};
+use sp1_vectorx_script::SP1_VECTOR_ELF;
+use tracing::warn;
+
+// Exporting requires the following environment variables to be set:
+// - AVAIL_URL: The URL of the Avail RPC endpoint.
+// - AVAIL_CHAIN_ID: The chain id of the Avail network.
+// - VECTORX_QUERY_URL: The URL of the VectorX query service.
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct InputsArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Export the inputs for a header range proof.
+    ExportHeaderRange {
+        /// Trusted block.
+        #[arg(long)]
+        trusted_block: u32,
+        /// Target block.
+        #[arg(long)]
+        target_block: u32,
+        /// Header range commitment tree size. Defaults to the next power of 2 of the number of headers.
+        #[arg(long)]
+        tree_size: Option<u32>,
+        /// Whether the target block is the last block justified by its authority set.
+        #[arg(long)]
+        target_epoch_end_block: bool,
+        /// Output path. Written as JSON if the extension is `.json`, otherwise bincode.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Export the inputs for a rotate proof.
+    ExportRotate {
+        /// The current authority set id.
+        #[arg(long)]
+        authority_set_id: u64,
+        /// Output path. Written as JSON if the extension is `.json`, otherwise bincode.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Execute the SP1 Vector program with the inputs from a file.
+    Execute {
+        /// Path to the proof inputs file.
+        #[arg(long)]
+        file: PathBuf,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    setup_logger();
+
+    let args = InputsArgs::parse();
+
+    match args.command {
+        Command::ExportHeaderRange {
+            trusted_block,
+            target_block,
+            tree_size,
+            target_epoch_end_block,
+            out,
+        } => {
+            let fetcher = RpcDataFetcher::new().await;
+            let header_range_request_data = HeaderRangeRequestData {
+                trusted_block,
+                target_block,
+                is_target_epoch_end_block: target_epoch_end_block,
+            };
+            // Export the inputs even if they fail the checks, so failing proofs can be reproduced.
+            let header_range_inputs = fetcher
+                .fetch_header_range_inputs(header_range_request_data, tree_size)
+                .await?;
+            if let Err(e) =
+                check_header_range_inputs(&header_range_inputs, header_range_request_data)
+            {
+                warn!("Header range inputs fail verification: {:?}", e);
+            }
+
+            ProofInputsFile::new(
+                &fetcher.avail_chain_id,
+                ProofInputs::HeaderRange(header_range_inputs),
+            )
+            .save(&out)?;
+            println!("Wrote header range inputs to {}", out.display());
+        }
+        Command::ExportRotate {
+            authority_set_id,
+            out,
+        } => {
+            let fetcher = RpcDataFetcher::new().await;
+            let rotate_inputs = fetcher.fetch_rotate_inputs(authority_set_id).await?;
+            if let Err(e) = check_rotate_inputs(&rotate_inputs, authority_set_id) {
+                warn!("Rotate inputs fail verification: {:?}", e);
+            }
+
+            ProofInputsFile::new(&fetcher.avail_chain_id, ProofInputs::Rotate(rotate_inputs))
+                .save(&out)?;
+            println!("Wrote rotate inputs to {}", out.display());
+        }
+        Command::Execute { file } => {
+            let proof_inputs_file = ProofInputsFile::load(&file)?;
+            let stdin = proof_inputs_file.inputs.to_stdin();
+
+            let client = ProverClient::from_env();
+            let (pv, report) = client.execute(SP1_VECTOR_ELF, &stdin).run()?;
+
+            let _ = ProofOutput::abi_decode(pv.as_slice())?;
+
+            println!("Execution Report: {:?}", report);
+            println!("Total instructions: {}", report.total_instruction_count());
+        }
+    }
+
+    Ok(())
+}
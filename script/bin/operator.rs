@@ -1,56 +1,51 @@
 use std::env;
-use std::time::Duration;
-use std::{cmp::min, collections::HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{
+    cmp::min,
+    collections::{BTreeMap, HashMap},
+};
 
-use alloy::network::{ReceiptResponse, TransactionBuilder};
+use alloy::network::{Ethereum, ReceiptResponse, TransactionBuilder};
 use alloy::signers::local::PrivateKeySigner;
 use alloy::{
     network::Network,
-    primitives::{Address, B256},
-    providers::{Provider, ProviderBuilder},
+    primitives::{keccak256, Address, B256},
+    providers::{DynProvider, Provider, ProviderBuilder},
     sol,
+    sol_types::{SolInterface, SolType, SolValue},
+    transports::TransportError,
 };
-use futures::future::{join_all, try_join_all};
+use clap::Parser;
+use futures::future::join_all;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use services::input::{HeaderRangeRequestData, RpcDataFetcher};
-use sp1_sdk::NetworkProver;
-use sp1_sdk::{
-    network::FulfillmentStrategy, HashableKey, Prover, ProverClient, SP1ProofWithPublicValues,
-    SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
-};
+use services::verify::{check_header_range_inputs, check_rotate_inputs};
+use sp1_sdk::{HashableKey, Prover, ProverClient, SP1ProofWithPublicValues};
 
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 use tracing_subscriber::EnvFilter;
 
 use services::Timeout;
-use sp1_vector_primitives::types::ProofType;
-use sp1_vectorx_script::relay::{self};
+use sp1_vector_primitives::types::{HeaderRangeOutputs, ProofOutput, ProofType, RotateOutputs};
+use sp1_vectorx_script::config::{ChainSettings, OperatorConfig, ProofSystem, SignerMode};
+use sp1_vectorx_script::fees::{FeePolicy, TxFees};
+use sp1_vectorx_script::inputs::{ProofInputs, ProofInputsFile};
+use sp1_vectorx_script::metrics::{proof_type_label, OperatorMetrics};
+use sp1_vectorx_script::pending_tx::{is_underpriced, PendingTx};
+use sp1_vectorx_script::proof_store::{ProofKey, ProofStore};
+use sp1_vectorx_script::prover::OperatorProver;
+use sp1_vectorx_script::relay::{self, KMSRelayError, KMSRelayStatus};
+use sp1_vectorx_script::scheduler::{self, ChainRequest};
 use sp1_vectorx_script::SP1_VECTOR_ELF;
 
-use config::{ChainConfig, SignerMode};
-
 ////////////////////////////////////////////////////////////
 // Constants
 ////////////////////////////////////////////////////////////
 
-// If the SP1 proof takes too long to respond, time out.
-const PROOF_TIMEOUT_SECS: u64 = 60 * 30;
-
-// If the operator takes too long to run, time out.
-const LOOP_TIMEOUT_MINS: u64 = 30;
-
-// If the RPC takes too long to respond, time out.
-const RPC_TIMEOUT_SECS: u64 = 60 * 2;
-
-// Wait for 3 required confirmations with a timeout of 60 seconds.
-const NUM_CONFIRMATIONS: u64 = 3;
-
-// If the relay takes too long to respond, time out.
-const RELAY_TIMEOUT_SECONDS: u64 = 60;
-
-// The number of times to retry a relay transaction.
-const NUM_RELAY_RETRIES: u32 = 3;
+// The backoff after a chain's first failure, doubled after each consecutive failure.
+const ERROR_INTERVAL: Duration = Duration::from_secs(10);
 
 ////////////////////////////////////////////////////////////
 // Type Definitions
@@ -59,6 +54,7 @@ const NUM_RELAY_RETRIES: u32 = 3;
 sol! {
     #[allow(missing_docs)]
     #[sol(rpc)]
+    #[derive(Debug)]
     contract SP1Vector {
         bool public frozen;
         uint32 public latestBlock;
@@ -70,19 +66,57 @@ sol! {
 
         function rotate(bytes calldata proof, bytes calldata publicValues) external;
         function commitHeaderRange(bytes calldata proof, bytes calldata publicValues) external;
+
+        error NextAuthoritySetExists();
+        error ContractFrozen();
+        error TrustedHeaderNotFound();
+        error TrustedHeaderMismatch();
+        error AuthoritySetNotFound();
+        error AuthoritySetMismatch();
+        error OldAuthoritySetId();
+        error InvalidProofType();
+        error InvalidMerkleTreeSize();
+        error BlockHeightMismatch();
+        error InvalidTargetBlock();
+        error RelayerNotApproved();
     }
 }
 
 type SP1VectorInstance<P, N> = SP1Vector::SP1VectorInstance<P, N>;
 
 struct SP1VectorOperator<P, N> {
-    pk: SP1ProvingKey,
-    vk: SP1VerifyingKey,
-    signer_mode: SignerMode,
-    tree_size: Option<u32>,
+    config: OperatorConfig,
     fetcher: RpcDataFetcher,
-    prover: NetworkProver,
+    prover: OperatorProver,
+    proof_store: Option<ProofStore>,
+    metrics: Arc<OperatorMetrics>,
     contracts: HashMap<u64, SP1VectorInstance<P, N>>,
+    chain_settings: HashMap<u64, ChainSettings>,
+    fee_policies: HashMap<u64, FeePolicy>,
+    health: Mutex<HashMap<u64, ChainHealth>>,
+    /// The address of the local signer, if there is one.
+    signer_address: Option<Address>,
+    /// The unconfirmed transaction of the local signer on each chain. Locked while relaying, so
+    /// a chain never has two transactions in flight.
+    pending_txs: HashMap<u64, tokio::sync::Mutex<Option<PendingTx>>>,
+}
+
+/// The health of a chain, updated after each iteration it ran in.
+#[derive(Debug)]
+struct ChainHealth {
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    /// The end of the backoff of a failing chain, which is retried on its own at this time. Chains
+    /// that are not failing run together on the operator loop.
+    retry_at: Option<Instant>,
+}
+
+/// The errors of the chains that failed during an iteration.
+type ChainFailures = HashMap<u64, anyhow::Error>;
+
+/// Record the error of a chain, keeping its first error if it already failed.
+fn record_failure(failures: &mut ChainFailures, chain_id: u64, error: anyhow::Error) {
+    failures.entry(chain_id).or_insert(error);
 }
 
 #[derive(Debug)]
@@ -93,9 +127,19 @@ struct HeaderRangeContractData {
     next_authority_set_hash_exists: bool,
 }
 
+/// Chains are served by the same header range proof only if they request the same range, with the
+/// same commitment tree size and proof system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct HeaderRangeProofKey {
+    request: HeaderRangeRequestData,
+    tree_size: u32,
+    proof_system: ProofSystem,
+}
+
 #[derive(Debug)]
 struct RotateContractData {
     current_block: u32,
+    latest_authority_set_id: u64,
     next_authority_set_hash_exists: bool,
 }
 
@@ -108,54 +152,54 @@ where
     P: Provider<N>,
     N: Network,
 {
-    async fn new(signer_mode: SignerMode) -> Self {
+    async fn new(config: OperatorConfig) -> Self {
         dotenv::dotenv().ok();
 
-        let prover = ProverClient::builder().network().build();
-        let (pk, vk) = prover.setup(SP1_VECTOR_ELF);
+        let prover = OperatorProver::new(&config.prover, SP1_VECTOR_ELF);
+
+        // Mock proofs are only valid for mock verifiers, so they are never stored.
+        let proof_store = match &config.proof_store_dir {
+            Some(_) if prover.is_mock() => {
+                warn!("Not storing proofs generated by the mock prover");
+                None
+            }
+            Some(dir) => Some(ProofStore::new(dir)),
+            None => None,
+        };
 
         Self {
             fetcher: RpcDataFetcher::new().await,
-            pk,
-            vk,
-            signer_mode,
+            config,
             prover,
+            proof_store,
+            metrics: Arc::new(OperatorMetrics::new().expect("Failed to create metrics")),
             contracts: HashMap::new(),
-            tree_size: None,
+            chain_settings: HashMap::new(),
+            fee_policies: HashMap::new(),
+            health: Mutex::new(HashMap::new()),
+            signer_address: None,
+            pending_txs: HashMap::new(),
         }
     }
 
     /// Register a new chain with the operator.
     ///
-    /// This function will panic if the tree size doesnt match as expected, or it fails to get the chain id.
-    async fn with_chain(mut self, provider: P, address: Address) -> Self {
+    /// This function will panic if it fails to get the chain id.
+    async fn with_chain(mut self, provider: P, address: Address, settings: ChainSettings) -> Self {
         let contract = SP1VectorInstance::new(address, provider);
 
-        let tree_size = contract
-            .headerRangeCommitmentTreeSize()
-            .call()
-            .await
-            .expect("Failed to get tree size");
-
         let chain_id = contract
             .provider()
             .get_chain_id()
             .await
             .expect("Failed to get chain id");
 
-        // Register the first tree size.
-        if self.tree_size.is_none() {
-            self.tree_size = Some(tree_size);
-        } else if self.tree_size.unwrap() != tree_size {
-            panic!(
-                "Tree size mismatch! Expected {}, got {} for chain id {}",
-                self.tree_size.unwrap(),
-                tree_size,
-                chain_id
-            );
-        }
-
         self.contracts.insert(chain_id, contract);
+        self.fee_policies
+            .insert(chain_id, FeePolicy::new(chain_id, settings.gas));
+        self.chain_settings.insert(chain_id, settings);
+        self.pending_txs
+            .insert(chain_id, tokio::sync::Mutex::new(None));
 
         self
     }
@@ -174,49 +218,56 @@ where
         &self,
         tree_size: u32,
         header_range_request: HeaderRangeRequestData,
+        proof_system: ProofSystem,
     ) -> Result<SP1ProofWithPublicValues> {
-        let mut stdin: SP1Stdin = SP1Stdin::new();
+        let key = ProofKey::HeaderRange {
+            trusted_block: header_range_request.trusted_block,
+            target_block: header_range_request.target_block,
+            tree_size,
+        };
+        if let Some(proof) = self.get_stored_proof(&key, proof_system) {
+            info!("Reusing stored header range proof {:?}.", key);
+            return Ok(proof);
+        }
 
-        let proof_type = ProofType::HeaderRangeProof;
         let header_range_inputs = self
             .fetcher
-            .get_header_range_inputs(header_range_request, Some(tree_size))
-            .await;
-
-        stdin.write(&proof_type);
-        stdin.write(&header_range_inputs);
+            .fetch_header_range_inputs(header_range_request, Some(tree_size))
+            .await?;
+        let name = format!(
+            "header-range-{}-{}",
+            header_range_request.trusted_block, header_range_request.target_block
+        );
+        if let Err(e) = check_header_range_inputs(&header_range_inputs, header_range_request) {
+            self.dump_inputs(ProofInputs::HeaderRange(header_range_inputs), &name, &e);
+            return Err(e);
+        }
 
         info!(
             "Requesting header range proof from block {} to block {}.",
             header_range_request.trusted_block, header_range_request.target_block
         );
 
-        // If the SP1_PROVER environment variable is set to "mock", use the mock prover.
-        if let Ok(prover_type) = env::var("SP1_PROVER") {
-            if prover_type == "mock" {
-                let prover_client = ProverClient::builder().mock().build();
-                let proof = prover_client.prove(&self.pk, &stdin).plonk().run()?;
-                return Ok(proof);
-            }
-        }
+        let proof = self
+            .prove(
+                ProofInputs::HeaderRange(header_range_inputs),
+                &name,
+                proof_system,
+            )
+            .await?;
+        self.store_proof(&key, proof_system, &proof);
 
-        self.prover
-            .prove(&self.pk, &stdin)
-            .strategy(FulfillmentStrategy::Reserved)
-            .skip_simulation(true)
-            .plonk()
-            .timeout(Duration::from_secs(PROOF_TIMEOUT_SECS))
-            .run_async()
-            .await
+        Ok(proof)
     }
 
-    // Ideally, post a header range update every ideal_block_interval blocks. Returns Option<(latest_block, block_to_step_to)>.
+    // Ideally, post a header range update every ideal_block_interval blocks. Returns the header range
+    // request with the contract's current commitment tree size, if there is a block to step to.
     #[instrument(skip(self, ideal_block_interval))]
     async fn find_header_range(
         &self,
         chain_id: u64,
         ideal_block_interval: u32,
-    ) -> Result<Option<HeaderRangeRequestData>> {
+    ) -> Result<Option<(HeaderRangeRequestData, u32)>> {
         let header_range_contract_data = self.get_contract_data_for_header_range(chain_id).await?;
         debug!(
             "header_range_contract_data: {:?}",
@@ -227,14 +278,14 @@ where
         let current_authority_set_id = self
             .fetcher
             .get_authority_set_id(header_range_contract_data.vectorx_latest_block - 1)
-            .await;
+            .await?;
 
         info!("current_authority_set_id: {}", current_authority_set_id);
         // Get the last justified block by the current authority set id.
         let last_justified_block = self
             .fetcher
             .last_justified_block(current_authority_set_id)
-            .await;
+            .await?;
 
         // If this is the last justified block, check for header range with next authority set.
         let mut request_authority_set_id = current_authority_set_id;
@@ -263,20 +314,40 @@ where
                 header_range_contract_data.avail_current_block,
                 request_authority_set_id,
             )
-            .await;
+            .await?;
 
         info!("Target Block: {:?}", maybe_block_to_step_to);
 
         if let Some(block_to_step_to) = maybe_block_to_step_to {
-            return Ok(Some(HeaderRangeRequestData {
-                trusted_block: header_range_contract_data.vectorx_latest_block,
-                target_block: block_to_step_to,
-                is_target_epoch_end_block: block_to_step_to == last_justified_block,
-            }));
+            return Ok(Some((
+                HeaderRangeRequestData {
+                    trusted_block: header_range_contract_data.vectorx_latest_block,
+                    target_block: block_to_step_to,
+                    is_target_epoch_end_block: block_to_step_to == last_justified_block,
+                },
+                header_range_contract_data.header_range_commitment_tree_size,
+            )));
         }
         Ok(None)
     }
 
+    /// Align the targets of the chains' header range requests with
+    /// [`scheduler::align_header_range_requests`].
+    fn align_header_range_requests(&self, requests: &[ChainRequest]) -> Vec<ChainRequest> {
+        let aligned = scheduler::align_header_range_requests(requests);
+
+        for (original, aligned) in requests.iter().zip(&aligned) {
+            if aligned.request != original.request {
+                info!(
+                    "Aligned the target of chain {} from block {} to block {}",
+                    original.chain_id, original.request.target_block, aligned.request.target_block
+                );
+            }
+        }
+
+        aligned
+    }
+
     // Current block, step_range_max and whether next authority set hash exists.
     async fn get_contract_data_for_header_range(
         &self,
@@ -293,10 +364,23 @@ where
 
         let avail_current_block = self.fetcher.get_head().await.number;
 
+        let chain_label = chain_id.to_string();
+        self.metrics
+            .latest_block
+            .with_label_values(&[chain_label.as_str()])
+            .set(vectorx_latest_block as i64);
+        self.metrics
+            .avail_head_block
+            .set(avail_current_block as i64);
+        self.metrics
+            .blocks_behind_head
+            .with_label_values(&[chain_label.as_str()])
+            .set(avail_current_block as i64 - vectorx_latest_block as i64);
+
         let vectorx_current_authority_set_id = self
             .fetcher
             .get_authority_set_id(vectorx_latest_block - 1)
-            .await;
+            .await?;
         let next_authority_set_id = vectorx_current_authority_set_id + 1;
 
         let next_authority_set_hash = contract
@@ -324,9 +408,8 @@ where
         vectorx_current_block: u32,
         avail_current_block: u32,
         authority_set_id: u64,
-    ) -> Option<u32> {
-        let fetcher = RpcDataFetcher::new().await;
-        let last_justified_block = fetcher.last_justified_block(authority_set_id).await;
+    ) -> Result<Option<u32>> {
+        let last_justified_block = self.fetcher.last_justified_block(authority_set_id).await?;
 
         // Step to the last justified block of the current epoch if it is in range. When the last
         // justified block is 0, the SP1Vector contract's latest epoch is the current epoch on the
@@ -335,7 +418,7 @@ where
             && last_justified_block <= vectorx_current_block + header_range_commitment_tree_size
         {
             debug!("last_justified_block: {}", last_justified_block);
-            return Some(last_justified_block);
+            return Ok(Some(last_justified_block));
         }
 
         // The maximum valid block to step to is the either header_range_commitment_tree_size blocks
@@ -351,40 +434,48 @@ where
 
         // Find the closest block to the maximum valid block to step to that is a multiple of
         // ideal_block_interval.
-        let mut block_to_step_to =
+        let block_to_step_to =
             max_valid_block_to_step_to - (max_valid_block_to_step_to % ideal_block_interval);
 
         debug!("Block to step to: {}", block_to_step_to);
 
         // If block_to_step_to is <= to the current block, return None.
         if block_to_step_to <= vectorx_current_block {
-            return None;
+            return Ok(None);
         }
 
-        // Check that block_to_step_to has a valid justification. If not, iterate up until the maximum_vectorx_target_block
-        // to find a valid justification. If we're unable to find a justification, something has gone
-        // deeply wrong with the justification indexer.
-        loop {
-            if block_to_step_to > max_valid_block_to_step_to {
-                error!(
-                    "Unable to find any valid justifications after searching from block {} to block {}. This is likely caused by an issue with the justification indexer.",
-                    vectorx_current_block + ideal_block_interval,
-                    max_valid_block_to_step_to
-                );
-                return None;
-            }
+        // Find the first block from block_to_step_to up to the maximum valid block to step to with a
+        // valid justification. If the justification indexer has gaps in this range, the discovery
+        // falls back to the justifications stored by the Avail node.
+        let discovery = self
+            .fetcher
+            .find_justified_block(block_to_step_to, max_valid_block_to_step_to)
+            .await;
 
-            if fetcher
-                .get_justification_data_for_block(block_to_step_to, false)
-                .await
-                .is_some()
-            {
-                break;
-            }
-            block_to_step_to += 1;
+        if !discovery.gaps.is_empty() {
+            warn!(
+                "Justification indexer is missing blocks between {} and {}: {:?}",
+                block_to_step_to, max_valid_block_to_step_to, discovery.gaps
+            );
+        }
+
+        let Some(justified_block) = discovery.justified_block else {
+            error!(
+                "Unable to find any valid justifications after searching from block {} to block {}. This is likely caused by an issue with the justification indexer.",
+                block_to_step_to,
+                max_valid_block_to_step_to
+            );
+            return Ok(None);
+        };
+
+        if discovery.from_finality_proof {
+            info!(
+                "Using finality proof from the Avail node for block {}",
+                justified_block
+            );
         }
 
-        Some(block_to_step_to)
+        Ok(Some(justified_block))
     }
 }
 
@@ -421,6 +512,7 @@ where
         // Return the fetched data
         Ok(RotateContractData {
             current_block: vectorx_latest_block,
+            latest_authority_set_id: vectorx_latest_authority_set_id,
             next_authority_set_hash_exists,
         })
     }
@@ -428,39 +520,37 @@ where
     async fn request_rotate(
         &self,
         current_authority_set_id: u64,
+        proof_system: ProofSystem,
     ) -> Result<SP1ProofWithPublicValues> {
-        let fetcher = RpcDataFetcher::new().await;
-
-        let mut stdin: SP1Stdin = SP1Stdin::new();
-
-        let proof_type = ProofType::RotateProof;
-        let rotate_input = fetcher.get_rotate_inputs(current_authority_set_id).await;
+        let key = ProofKey::Rotate {
+            authority_set_id: current_authority_set_id,
+        };
+        if let Some(proof) = self.get_stored_proof(&key, proof_system) {
+            info!("Reusing stored rotate proof {:?}.", key);
+            return Ok(proof);
+        }
 
-        stdin.write(&proof_type);
-        stdin.write(&rotate_input);
+        let rotate_input = self
+            .fetcher
+            .fetch_rotate_inputs(current_authority_set_id)
+            .await?;
+        let name = format!("rotate-{}", current_authority_set_id);
+        if let Err(e) = check_rotate_inputs(&rotate_input, current_authority_set_id) {
+            self.dump_inputs(ProofInputs::Rotate(rotate_input), &name, &e);
+            return Err(e);
+        }
 
         info!(
             "Requesting rotate proof to add authority set {}.",
             current_authority_set_id + 1
         );
 
-        // If the SP1_PROVER environment variable is set to "mock", use the mock prover.
-        if let Ok(prover_type) = env::var("SP1_PROVER") {
-            if prover_type == "mock" {
-                let prover_client = ProverClient::builder().mock().build();
-                let proof = prover_client.prove(&self.pk, &stdin).plonk().run()?;
-                return Ok(proof);
-            }
-        }
+        let proof = self
+            .prove(ProofInputs::Rotate(rotate_input), &name, proof_system)
+            .await?;
+        self.store_proof(&key, proof_system, &proof);
 
-        self.prover
-            .prove(&self.pk, &stdin)
-            .strategy(FulfillmentStrategy::Reserved)
-            .skip_simulation(true)
-            .plonk()
-            .timeout(Duration::from_secs(PROOF_TIMEOUT_SECS))
-            .run_async()
-            .await
+        Ok(proof)
     }
 
     // Determine if a rotate is needed and request the proof if so. Returns Option<current_authority_set_id>.
@@ -475,14 +565,29 @@ where
         let head_block = self.fetcher.get_head().await.number;
         debug!("head_block: {}", head_block);
 
-        let head_authority_set_id = self.fetcher.get_authority_set_id(head_block - 1).await;
+        let head_authority_set_id = self.fetcher.get_authority_set_id(head_block - 1).await?;
         debug!("head_authority_set_id: {}", head_authority_set_id);
 
+        let chain_label = chain_id.to_string();
+        self.metrics
+            .latest_authority_set_id
+            .with_label_values(&[chain_label.as_str()])
+            .set(rotate_contract_data.latest_authority_set_id as i64);
+        self.metrics
+            .avail_authority_set_id
+            .set(head_authority_set_id as i64);
+        self.metrics
+            .authority_sets_behind_head
+            .with_label_values(&[chain_label.as_str()])
+            .set(
+                head_authority_set_id as i64 - rotate_contract_data.latest_authority_set_id as i64,
+            );
+
         // The current authority set id is the authority set id of the block before the current block.
         let current_authority_set_id = self
             .fetcher
             .get_authority_set_id(rotate_contract_data.current_block - 1)
-            .await;
+            .await?;
         debug!("current_authority_set_id: {}", current_authority_set_id);
 
         if current_authority_set_id < head_authority_set_id
@@ -494,6 +599,123 @@ where
     }
 }
 
+////////////////////////////////////////////////////////////
+// Proving
+////////////////////////////////////////////////////////////
+
+impl<P, N> SP1VectorOperator<P, N>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    /// Get a previously generated proof from the proof store, if there is one.
+    fn get_stored_proof(
+        &self,
+        key: &ProofKey,
+        proof_system: ProofSystem,
+    ) -> Option<SP1ProofWithPublicValues> {
+        let proof_store = self.proof_store.as_ref()?;
+
+        match proof_store.get(
+            &self.fetcher.avail_chain_id,
+            &self.prover.vk().bytes32(),
+            proof_system,
+            key,
+        ) {
+            Ok(proof) => proof,
+            Err(e) => {
+                warn!("Failed to read stored proof {:?}: {:?}", key, e);
+                None
+            }
+        }
+    }
+
+    /// Save a generated proof to the proof store, so it can be relayed again without proving.
+    fn store_proof(
+        &self,
+        key: &ProofKey,
+        proof_system: ProofSystem,
+        proof: &SP1ProofWithPublicValues,
+    ) {
+        let Some(proof_store) = &self.proof_store else {
+            return;
+        };
+
+        if let Err(e) = proof_store.put(
+            &self.fetcher.avail_chain_id,
+            &self.prover.vk().bytes32(),
+            proof_system,
+            key,
+            proof,
+        ) {
+            error!("Failed to store proof {:?}: {:?}", key, e);
+        }
+    }
+
+    /// If `proof_inputs_dump_dir` is configured, save the inputs that failed verification or proving
+    /// to a file in that directory, so the failure can be reproduced offline with the `inputs`
+    /// binary.
+    fn dump_inputs(&self, inputs: ProofInputs, name: &str, error: &anyhow::Error) {
+        let Some(dump_dir) = &self.config.proof_inputs_dump_dir else {
+            return;
+        };
+
+        let path = dump_dir.join(format!(
+            "{}-{}.bin",
+            name,
+            chrono::Utc::now().format("%Y%m%dT%H%M%S")
+        ));
+        match ProofInputsFile::new(&self.fetcher.avail_chain_id, inputs).save(&path) {
+            Ok(()) => info!("Saved failed inputs to {}: {:?}", path.display(), error),
+            Err(save_err) => error!(
+                "Failed to save failed inputs to {}: {:?}",
+                path.display(),
+                save_err
+            ),
+        }
+    }
+
+    /// Generate a proof for the given inputs, saving them with [`Self::dump_inputs`] if proving
+    /// fails.
+    async fn prove(
+        &self,
+        inputs: ProofInputs,
+        name: &str,
+        proof_system: ProofSystem,
+    ) -> Result<SP1ProofWithPublicValues> {
+        let stdin = inputs.to_stdin();
+        let proof_type = proof_type_label(&inputs.proof_type());
+
+        let start_time = Instant::now();
+        let result = self
+            .prover
+            .prove(
+                &stdin,
+                proof_system,
+                Duration::from_secs(self.config.proof_timeout_secs),
+            )
+            .await;
+        match &result {
+            Ok(_) => self
+                .metrics
+                .proof_duration_seconds
+                .with_label_values(&[proof_type])
+                .observe(start_time.elapsed().as_secs_f64()),
+            Err(_) => self
+                .metrics
+                .proof_failures
+                .with_label_values(&[proof_type])
+                .inc(),
+        }
+
+        if let Err(e) = &result {
+            self.dump_inputs(inputs, name, e);
+        }
+
+        result
+    }
+}
+
 ////////////////////////////////////////////////////////////
 // Control Flow & SP1
 ////////////////////////////////////////////////////////////
@@ -503,34 +725,61 @@ where
     P: Provider<N>,
     N: Network,
 {
-    /// Create and relay a header range proof for each chain.
+    /// Create and relay a header range proof for each of the given chains.
     ///
-    /// If any step of this function fails, it will return a generic error indicating a failure.
-    async fn handle_header_range(&self) -> Result<()> {
-        let block_interval = get_block_update_interval();
-
-        // NOTE: Fails fast if any of the futures fail.
-        let header_range_datas =
-            try_join_all(self.contracts.keys().copied().map(|id| async move {
-                Result::<_, anyhow::Error>::Ok((
-                    id,
-                    self.find_header_range(id, block_interval).await?,
-                ))
-            }))
-            .timeout(Duration::from_secs(RPC_TIMEOUT_SECS))
-            .await??;
-
-        // Batch the chains with the same header range request data.
-        let mut header_range_data_to_chain_id: HashMap<_, Vec<u64>> = HashMap::new();
-        header_range_datas
-            .into_iter()
-            .filter(|(_, header_range_data)| header_range_data.is_some())
-            .for_each(|(id, header_range_data)| {
-                header_range_data_to_chain_id
-                    .entry(header_range_data.unwrap())
-                    .or_default()
-                    .push(id);
-            });
+    /// Returns the chains that failed, with their errors. A failure on one chain does not stop the
+    /// other chains from progressing.
+    async fn handle_header_range(&self, chain_ids: &[u64]) -> ChainFailures {
+        let mut failures = ChainFailures::new();
+
+        let header_range_datas = join_all(chain_ids.iter().copied().map(|id| async move {
+            let block_interval = self.chain_settings[&id].block_update_interval;
+            let result = self
+                .find_header_range(id, block_interval)
+                .timeout(Duration::from_secs(self.config.rpc_timeout_secs))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            (id, result)
+        }))
+        .await;
+
+        let mut requests = Vec::new();
+        for (id, header_range_data) in header_range_datas {
+            match header_range_data {
+                Ok(Some((request, tree_size))) => requests.push(ChainRequest {
+                    chain_id: id,
+                    request,
+                    tree_size,
+                }),
+                Ok(None) => {}
+                Err(e) => {
+                    record_failure(&mut failures, id, e.context("Failed to find header range"))
+                }
+            }
+        }
+
+        // Align the targets of the chains so they can share proofs.
+        let requests = self.align_header_range_requests(&requests);
+
+        // Batch the chains with the same header range request data, tree size and proof system.
+        let mut header_range_data_to_chain_id: HashMap<HeaderRangeProofKey, Vec<u64>> =
+            HashMap::new();
+        for ChainRequest {
+            chain_id,
+            request,
+            tree_size,
+        } in requests
+        {
+            header_range_data_to_chain_id
+                .entry(HeaderRangeProofKey {
+                    request,
+                    tree_size,
+                    proof_system: self.chain_settings[&chain_id].proof_system,
+                })
+                .or_default()
+                .push(chain_id);
+        }
 
         debug!(
             "header_range_data_to_chain_id: {:?}",
@@ -539,17 +788,18 @@ where
 
         // Create a single proof for all the chain with the same header range request data, then relay to each chain.
         let results = join_all(header_range_data_to_chain_id.into_iter().map(
-            |(header_range_data, chain_ids)| async move {
-                let proof = self
-                    .request_header_range(
-                        self.tree_size.expect("Tree size not set"),
-                        header_range_data,
-                    )
-                    .await?;
+            |(key, chain_ids)| async move {
+                let proof = match self
+                    .request_header_range(key.tree_size, key.request, key.proof_system)
+                    .await
+                {
+                    Ok(proof) => proof,
+                    Err(e) => return Err((chain_ids, e)),
+                };
 
                 info!(
                     "Created header range proof for chain {:?} of {:?}",
-                    chain_ids, header_range_data
+                    chain_ids, key
                 );
 
                 // Relay the transaction to all chains.
@@ -568,89 +818,87 @@ where
                             )
                             .into_transaction_request();
 
-                        async move {
-                            match self
-                                .relay_tx(chain_id, tx)
-                                .await
-                                .context(format!("Relaying proof for chain {chain_id} failed"))
-                            {
-                                Ok(tx_hash) => Ok((chain_id, tx_hash)),
-                                Err(e) => Err(e),
-                            }
-                        }
+                        async move { (chain_id, self.relay_tx(chain_id, tx).await) }
                     })
                     .collect();
 
-                Result::<_, anyhow::Error>::Ok(join_all(tx_hash_futs).await)
+                Ok(join_all(tx_hash_futs).await)
             },
         ))
         .await;
 
-        // Check if any of the futures failed.
-        // There are two cases where a future can fail here:
-        // - Creating the rotate proof failed.
+        // There are two cases where a chain can fail here:
+        // - Creating the header range proof failed, which fails every chain of the batch.
         // - Relaying the transaction failed.
-        //
-        // In either case log it and indicate the failure to the caller.
-        let mut has_errors = false;
         for batch_result in results {
-            if let Err(e) = batch_result {
-                has_errors = true;
-                error!("Error creating rotate proof: {:?}", e);
-            } else {
-                for relay_result in batch_result.unwrap() {
-                    if let Ok((chain_id, tx_hash)) = relay_result {
-                        info!(
-                            "Posted next header range on chain {}\nTransaction hash: {}",
-                            chain_id, tx_hash
-                        );
-                    } else {
-                        has_errors = true;
-                        error!(
-                            "Error relaying rotate proof: {:?}",
-                            relay_result.unwrap_err()
+            match batch_result {
+                Ok(relay_results) => {
+                    for (chain_id, relay_result) in relay_results {
+                        match relay_result {
+                            Ok(tx_hash) => info!(
+                                "Posted next header range on chain {}\nTransaction hash: {}",
+                                chain_id, tx_hash
+                            ),
+                            Err(e) => record_failure(
+                                &mut failures,
+                                chain_id,
+                                e.context("Failed to relay header range proof"),
+                            ),
+                        }
+                    }
+                }
+                Err((chain_ids, e)) => {
+                    for chain_id in chain_ids {
+                        record_failure(
+                            &mut failures,
+                            chain_id,
+                            anyhow::anyhow!("Failed to create header range proof: {:#}", e),
                         );
                     }
                 }
             }
         }
 
-        if has_errors {
-            return Err(anyhow::anyhow!("Error during `handle_header_range`!"));
-        }
-
-        Ok(())
+        failures
     }
 
-    /// Create and relay proof for each chain of an authority set rotation.
+    /// Create and relay proof for each of the given chains of an authority set rotation.
     ///
-    /// If any step of this function fails, it will return a generic error indicating a failure.
-    async fn handle_rotate(&self) -> Result<()> {
+    /// Returns the chains that failed, with their errors. A failure on one chain does not stop the
+    /// other chains from progressing.
+    async fn handle_rotate(&self, chain_ids: &[u64]) -> ChainFailures {
         debug!("Enter handle rotate");
+        let mut failures = ChainFailures::new();
 
-        let next_authority_set_ids = self.contracts.keys().copied().map(|id| async move {
-            Result::<_, anyhow::Error>::Ok((id, self.find_rotate(id).await?))
-        });
-
-        // NOTE: Fails fast if any of the futures fail.
-        let next_authority_set_ids = try_join_all(next_authority_set_ids)
-            .timeout(Duration::from_secs(RPC_TIMEOUT_SECS))
-            .await??;
+        let next_authority_set_ids = join_all(chain_ids.iter().copied().map(|id| async move {
+            let result = self
+                .find_rotate(id)
+                .timeout(Duration::from_secs(self.config.rpc_timeout_secs))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            (id, result)
+        }))
+        .await;
 
-        // "Batch" the chains by the next authority set id.
-        let mut next_authority_set_to_chain_ids_map: HashMap<u64, Vec<u64>> =
+        // "Batch" the chains by the next authority set id and proof system.
+        let mut next_authority_set_to_chain_ids_map: HashMap<(u64, ProofSystem), Vec<u64>> =
             HashMap::with_capacity(next_authority_set_ids.len());
-
-        // Populate the map with the next authority set ids.
-        next_authority_set_ids
-            .into_iter()
-            .filter(|(_, next_authority_set_id)| next_authority_set_id.is_some())
-            .for_each(|(chain_id, next_authority_set_id)| {
-                next_authority_set_to_chain_ids_map
-                    .entry(next_authority_set_id.unwrap())
+        for (chain_id, next_authority_set_id) in next_authority_set_ids {
+            match next_authority_set_id {
+                Ok(Some(next_authority_set_id)) => next_authority_set_to_chain_ids_map
+                    .entry((
+                        next_authority_set_id,
+                        self.chain_settings[&chain_id].proof_system,
+                    ))
                     .or_default()
-                    .push(chain_id);
-            });
+                    .push(chain_id),
+                Ok(None) => {}
+                Err(e) => {
+                    record_failure(&mut failures, chain_id, e.context("Failed to find rotate"))
+                }
+            }
+        }
 
         debug!(
             "next_authority_set_to_chain_ids_map: {:?}",
@@ -659,11 +907,11 @@ where
 
         // Create and relay a proof for each back to all the chains concurrently.
         let results = join_all(next_authority_set_to_chain_ids_map.into_iter().map(
-            |(next_auth_id, chain_ids)| async move {
-                let proof = self.request_rotate(next_auth_id).await.context(format!(
-                    "Failed to request rotate proof for chains {:?}",
-                    chain_ids
-                ))?;
+            |((next_auth_id, proof_system), chain_ids)| async move {
+                let proof = match self.request_rotate(next_auth_id, proof_system).await {
+                    Ok(proof) => proof,
+                    Err(e) => return Err((chain_ids, e)),
+                };
 
                 info!(
                     "Created rotate proof for authority set {} on chains {:?}",
@@ -683,58 +931,48 @@ where
                             .rotate(proof.bytes().into(), proof.public_values.to_vec().into())
                             .into_transaction_request();
 
-                        async move {
-                            match self
-                                .relay_tx(chain_id, tx)
-                                .await
-                                .context(format!("Relaying proof for chain {chain_id} failed"))
-                            {
-                                Ok(tx_hash) => Ok((chain_id, tx_hash)),
-                                Err(e) => Err(e),
-                            }
-                        }
+                        async move { (chain_id, self.relay_tx(chain_id, tx).await) }
                     })
                     .collect();
 
-                Result::<_, anyhow::Error>::Ok(join_all(tx_hash_futs).await)
+                Ok(join_all(tx_hash_futs).await)
             },
         ))
         .await;
 
-        // Check if any of the futures failed.
-        // There are two cases where a future can fail here:
-        // - Creating the rotate proof failed.
+        // There are two cases where a chain can fail here:
+        // - Creating the rotate proof failed, which fails every chain of the batch.
         // - Relaying the transaction failed.
-        //
-        // In either case log it and indicate the failure to the caller.
-        let mut has_errors = false;
         for batch_result in results {
-            if let Err(e) = batch_result {
-                has_errors = true;
-                error!("Error creating rotate proof: {:?}", e);
-            } else {
-                for relay_result in batch_result.unwrap() {
-                    if let Ok((chain_id, tx_hash)) = relay_result {
-                        info!(
-                            "Posted next authority set on chain {}\nTransaction hash: {}",
-                            chain_id, tx_hash
-                        );
-                    } else {
-                        has_errors = true;
-                        error!(
-                            "Error relaying rotate proof: {:?}",
-                            relay_result.unwrap_err()
+            match batch_result {
+                Ok(relay_results) => {
+                    for (chain_id, relay_result) in relay_results {
+                        match relay_result {
+                            Ok(tx_hash) => info!(
+                                "Posted next authority set on chain {}\nTransaction hash: {}",
+                                chain_id, tx_hash
+                            ),
+                            Err(e) => record_failure(
+                                &mut failures,
+                                chain_id,
+                                e.context("Failed to relay rotate proof"),
+                            ),
+                        }
+                    }
+                }
+                Err((chain_ids, e)) => {
+                    for chain_id in chain_ids {
+                        record_failure(
+                            &mut failures,
+                            chain_id,
+                            anyhow::anyhow!("Failed to create rotate proof: {:#}", e),
                         );
                     }
                 }
             }
         }
 
-        if has_errors {
-            Err(anyhow::anyhow!("Error during `handle_rotate`!"))
-        } else {
-            Ok(())
-        }
+        failures
     }
 
     /// Relay a transaction to the given chain id.
@@ -744,31 +982,85 @@ where
     async fn relay_tx(&self, chain_id: u64, tx: N::TransactionRequest) -> Result<B256> {
         debug!("Relaying transaction to chain {}", chain_id);
 
-        if matches!(self.signer_mode, SignerMode::Kms) {
-            relay::relay_with_kms(
-                &relay::KMSRelayRequest {
+        let settings = &self.chain_settings[&chain_id];
+        let chain_label = chain_id.to_string();
+        if matches!(settings.signer_mode, SignerMode::Kms) {
+            let result = async {
+                // The relayer simulates and estimates the gas of the transaction itself, as it is
+                // the only approved sender. The base fee limit still applies, and the fee caps are
+                // sent to relayers that support them.
+                let fee_policy = &self.fee_policies[&chain_id];
+                let provider = self
+                    .contracts
+                    .get(&chain_id)
+                    .expect("No contract for chain id")
+                    .provider();
+                let base_fee = fee_policy.wait_for_base_fee(provider).await?;
+
+                let mut request = relay::KMSRelayRequest {
                     chain_id,
                     address: tx.to().expect("Transaction has no to address").to_string(),
                     calldata: tx.input().expect("Transaction has no input").to_string(),
                     platform_request: false,
-                },
-                NUM_RELAY_RETRIES,
-            )
-            .await
+                    gas_limit: None,
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
+                };
+                if fee_policy.gas.kms_gas_fields.unwrap_or_default() {
+                    let fees =
+                        fee_policy.fees(base_fee, provider.get_max_priority_fee_per_gas().await?);
+                    request.gas_limit = fee_policy.gas.gas_limit;
+                    request.max_fee_per_gas = Some(fees.max_fee_per_gas);
+                    request.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+                }
+
+                relay::relay_with_kms(&request, settings.num_relay_retries).await
+            }
+            .await;
+
+            let status = match &result {
+                Ok(_) => KMSRelayStatus::Relayed.as_str(),
+                Err(e) => e
+                    .downcast_ref::<KMSRelayError>()
+                    .map_or("error", |e| e.status.as_str()),
+            };
+            self.metrics
+                .relays
+                .with_label_values(&[chain_label.as_str(), status])
+                .inc();
+
+            result
         } else {
-            let contract = self
-                .contracts
-                .get(&chain_id)
-                .expect("No contract for chain id");
-
-            let receipt = contract
-                .provider()
-                .send_transaction(tx)
-                .await?
-                .with_required_confirmations(NUM_CONFIRMATIONS)
-                .with_timeout(Some(Duration::from_secs(RELAY_TIMEOUT_SECONDS)))
-                .get_receipt()
-                .await?;
+            let receipt = match self.send_local_tx(chain_id, tx).await {
+                Ok(receipt) => receipt,
+                Err(e) => {
+                    self.metrics
+                        .relays
+                        .with_label_values(&[chain_label.as_str(), "error"])
+                        .inc();
+                    return Err(e);
+                }
+            };
+
+            let gas_used = receipt.gas_used();
+            let fees_paid_gwei = gas_used as u128 * receipt.effective_gas_price() / 1_000_000_000;
+            self.metrics
+                .gas_used
+                .with_label_values(&[chain_label.as_str()])
+                .inc_by(gas_used);
+            self.metrics
+                .fees_paid_gwei
+                .with_label_values(&[chain_label.as_str()])
+                .inc_by(fees_paid_gwei as u64);
+            let status = if receipt.status() {
+                "success"
+            } else {
+                "reverted"
+            };
+            self.metrics
+                .relays
+                .with_label_values(&[chain_label.as_str(), status])
+                .inc();
 
             if !receipt.status() {
                 return Err(anyhow::anyhow!("Transaction reverted!"));
@@ -778,6 +1070,214 @@ where
         }
     }
 
+    /// Send a transaction with the local signer and wait for its receipt.
+    ///
+    /// The transaction takes the nonce of the chain's pending transaction if there is one, replacing
+    /// it: the pending transaction was built from the same contract state, as it was never mined, so
+    /// it is superseded. If the transaction is not confirmed within the relay timeout, it is replaced
+    /// with bumped fees up to `num_fee_bumps` times, then left pending for the next loop to replace.
+    async fn send_local_tx(
+        &self,
+        chain_id: u64,
+        tx: N::TransactionRequest,
+    ) -> Result<N::ReceiptResponse> {
+        let settings = &self.chain_settings[&chain_id];
+        let from = self
+            .signer_address
+            .ok_or_else(|| anyhow::anyhow!("No local signer for chain {}", chain_id))?;
+        let provider = self
+            .contracts
+            .get(&chain_id)
+            .expect("No contract for chain id")
+            .provider();
+        let mut pending = self.pending_txs[&chain_id].lock().await;
+
+        let (mut nonce, _) = self
+            .reconcile_pending_tx(chain_id, from, &mut pending)
+            .await?;
+
+        let fee_policy = &self.fee_policies[&chain_id];
+        let mut tx = tx;
+        tx.set_from(from);
+        let TxFees {
+            gas_limit,
+            fees: current_fees,
+        } = fee_policy.estimate(provider, &tx).await?;
+        let mut fees = match pending.as_ref() {
+            Some(pending_tx) => fee_policy.cap(
+                pending_tx
+                    .fees
+                    .bumped(current_fees, settings.fee_bump_percent),
+            ),
+            None => current_fees,
+        };
+
+        let mut sent_tx_hashes = Vec::new();
+        for attempt in 0..=settings.num_fee_bumps {
+            if attempt > 0 {
+                let bumped = fee_policy.cap(fees.bumped(fees, settings.fee_bump_percent));
+                if bumped == fees {
+                    return Err(anyhow::anyhow!(
+                        "Transaction at nonce {} on chain {} is not confirmed, and its fees are at the cap",
+                        nonce,
+                        chain_id
+                    ));
+                }
+                fees = bumped;
+            }
+
+            let mut tx = tx.clone();
+            tx.set_nonce(nonce);
+            tx.set_max_fee_per_gas(fees.max_fee_per_gas);
+            tx.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+            tx.set_gas_limit(gas_limit);
+
+            let sent = match provider.send_transaction(tx).await {
+                Ok(sent) => sent,
+                // The transaction being replaced is unknown after a restart, so the first fees may
+                // not be enough to replace it.
+                Err(e) if attempt < settings.num_fee_bumps && is_underpriced(&e) => {
+                    warn!(
+                        "Replacement at nonce {} on chain {} is underpriced, bumping fees: {}",
+                        nonce, chain_id, e
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let tx_hash = *sent.tx_hash();
+            sent_tx_hashes.push(tx_hash);
+            PendingTx::record(&mut pending, nonce, tx_hash, fees);
+            info!(
+                "Sent transaction {} at nonce {} on chain {} with max fee {} and priority fee {}",
+                tx_hash, nonce, chain_id, fees.max_fee_per_gas, fees.max_priority_fee_per_gas
+            );
+
+            match sent
+                .with_required_confirmations(settings.num_confirmations)
+                .with_timeout(Some(Duration::from_secs(settings.relay_timeout_secs)))
+                .get_receipt()
+                .await
+            {
+                Ok(receipt) => {
+                    *pending = None;
+                    return Ok(receipt);
+                }
+                Err(e) => warn!(
+                    "Transaction {} on chain {} not confirmed: {}",
+                    tx_hash, chain_id, e
+                ),
+            }
+
+            // One of the transactions at this nonce may have been mined while waiting.
+            let (next_nonce, mined) = self
+                .reconcile_pending_tx(chain_id, from, &mut pending)
+                .await?;
+            if let Some(receipt) = mined {
+                if sent_tx_hashes.contains(&receipt.transaction_hash()) {
+                    return Ok(receipt);
+                }
+                return Err(anyhow::anyhow!(
+                    "A transaction of a previous loop was mined at nonce {} on chain {}: {}",
+                    nonce,
+                    chain_id,
+                    receipt.transaction_hash()
+                ));
+            }
+            nonce = next_nonce;
+        }
+
+        Err(anyhow::anyhow!(
+            "Transaction at nonce {} on chain {} not confirmed after {} fee bumps",
+            nonce,
+            chain_id,
+            settings.num_fee_bumps
+        ))
+    }
+
+    /// Reconcile the pending transaction of a chain with the chain, and return the nonce of the next
+    /// transaction with the receipt of the pending transaction if it was mined.
+    ///
+    /// - If the pending transaction's nonce was used, one of its transactions was mined, or another
+    ///   transaction replaced it. Either way, it is no longer pending.
+    /// - If a pending transaction is unknown to the node, it was dropped, and is sent again at the
+    ///   same nonce.
+    /// - If there is no pending transaction, as after a restart, but the signer has a transaction in
+    ///   the mempool, it is replaced. If it has several, an error is returned, as only the first
+    ///   could be replaced.
+    async fn reconcile_pending_tx(
+        &self,
+        chain_id: u64,
+        from: Address,
+        pending: &mut Option<PendingTx>,
+    ) -> Result<(u64, Option<N::ReceiptResponse>)> {
+        let provider = self
+            .contracts
+            .get(&chain_id)
+            .expect("No contract for chain id")
+            .provider();
+        let confirmed_nonce = provider.get_transaction_count(from).await?;
+
+        let Some(pending_tx) = pending.clone() else {
+            let pending_nonce = provider.get_transaction_count(from).pending().await?;
+            match pending_nonce.saturating_sub(confirmed_nonce) {
+                0 => {}
+                1 => warn!(
+                    "A transaction of {} is pending on chain {}, replacing it at nonce {}",
+                    from, chain_id, confirmed_nonce
+                ),
+                // Replacing the first transaction would leave the later ones to be mined after it.
+                num_pending => {
+                    return Err(anyhow::anyhow!(
+                        "{} transactions of {} are pending on chain {} at nonces {} to {}, not sending until they are mined or cancelled",
+                        num_pending,
+                        from,
+                        chain_id,
+                        confirmed_nonce,
+                        pending_nonce - 1
+                    ))
+                }
+            }
+            return Ok((confirmed_nonce, None));
+        };
+
+        if pending_tx.nonce < confirmed_nonce {
+            for tx_hash in pending_tx.tx_hashes.iter().rev() {
+                if let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? {
+                    info!(
+                        "Pending transaction {} at nonce {} on chain {} was mined",
+                        tx_hash, pending_tx.nonce, chain_id
+                    );
+                    *pending = None;
+                    return Ok((confirmed_nonce, Some(receipt)));
+                }
+            }
+
+            warn!(
+                "Pending transaction at nonce {} on chain {} was replaced by another transaction",
+                pending_tx.nonce, chain_id
+            );
+            *pending = None;
+            return Ok((confirmed_nonce, None));
+        }
+
+        let mut known = false;
+        for tx_hash in &pending_tx.tx_hashes {
+            if provider.get_transaction_by_hash(*tx_hash).await?.is_some() {
+                known = true;
+                break;
+            }
+        }
+        if !known {
+            warn!(
+                "Pending transaction at nonce {} on chain {} was dropped, sending it again",
+                pending_tx.nonce, chain_id
+            );
+        }
+
+        Ok((pending_tx.nonce, None))
+    }
+
     /// Check the verifying key in the contract matches the
     /// verifying key in the prover for the given `chain_id`.
     async fn check_vkey(&self, chain_id: u64) -> Result<()> {
@@ -792,7 +1292,7 @@ where
         let verifying_key = contract.vectorXProgramVkey().call().await?;
 
         if verifying_key.0.to_vec()
-            != hex::decode(self.vk.bytes32().strip_prefix("0x").unwrap()).unwrap()
+            != hex::decode(self.prover.vk().bytes32().strip_prefix("0x").unwrap()).unwrap()
         {
             return Err(anyhow::anyhow!(
                 "The verifying key in the operator does not match the verifying key in the contract!"
@@ -802,202 +1302,614 @@ where
         Ok(())
     }
 
-    /// Run a single iteration of the operator.
+    /// Run a single iteration of the operator for the given chains.
     ///
-    /// If any step of this function fails, it will return a generic error indicating a failure.
-    async fn run_once(&self) -> Result<()> {
-        debug!("Starting operator, run_once");
-        let mut has_errors = false;
+    /// Returns the chains that failed, with their errors. A chain whose verifying key check fails is
+    /// skipped for the rest of the iteration.
+    async fn run_once(&self, chain_ids: &[u64]) -> ChainFailures {
+        debug!("Starting operator, run_once for chains {:?}", chain_ids);
+        let mut failures = ChainFailures::new();
+
+        let vkey_results = join_all(chain_ids.iter().copied().map(|id| async move {
+            let result = self
+                .check_vkey(id)
+                .timeout(Duration::from_secs(self.config.rpc_timeout_secs))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            (id, result)
+        }))
+        .await;
+        for (chain_id, result) in vkey_results {
+            if let Err(e) = result {
+                record_failure(&mut failures, chain_id, e);
+            }
+        }
 
-        // NOTE: Fails fast if any of the futures fail.
-        try_join_all(self.contracts.keys().copied().map(|id| self.check_vkey(id))).await?;
+        let chain_ids: Vec<u64> = chain_ids
+            .iter()
+            .copied()
+            .filter(|id| !failures.contains_key(id))
+            .collect();
 
-        if let Err(e) = self.handle_rotate().await {
-            has_errors = true;
-            error!("Error during `handle_rotate`: {:?}", e);
+        for (chain_id, e) in self.handle_rotate(&chain_ids).await {
+            record_failure(&mut failures, chain_id, e);
         }
-
-        if let Err(e) = self.handle_header_range().await {
-            has_errors = true;
-            error!("Error during `handle_header_range`: {:?}", e);
+        for (chain_id, e) in self.handle_header_range(&chain_ids).await {
+            record_failure(&mut failures, chain_id, e);
         }
 
-        if has_errors {
-            // By this point, any known errors have been logged.
-            return Err(anyhow::anyhow!(""));
+        failures
+    }
+
+    /// The chains that are due to run: the chains that are not failing if the operator loop is due,
+    /// and the failing chains whose backoff has elapsed.
+    fn due_chains(&self, loop_due: bool) -> Vec<u64> {
+        let now = Instant::now();
+        let health = self.health.lock().unwrap();
+
+        self.contracts
+            .keys()
+            .copied()
+            .filter(|id| match health.get(id).and_then(|h| h.retry_at) {
+                Some(retry_at) => retry_at <= now,
+                None => loop_due,
+            })
+            .collect()
+    }
+
+    /// Update the health of the chains that ran. A failing chain is retried after an exponential
+    /// backoff capped at the loop interval, and a chain that succeeded rejoins the operator loop, so
+    /// the chains stay batched together.
+    fn update_health(&self, chain_ids: &[u64], mut failures: ChainFailures) {
+        let now = Instant::now();
+        let loop_interval = Duration::from_secs(self.config.loop_interval_mins * 60);
+        let mut health = self.health.lock().unwrap();
+
+        for &chain_id in chain_ids {
+            let chain_health = health.entry(chain_id).or_insert_with(|| ChainHealth {
+                consecutive_failures: 0,
+                last_error: None,
+                retry_at: None,
+            });
+
+            match failures.remove(&chain_id) {
+                Some(e) => {
+                    chain_health.consecutive_failures += 1;
+                    self.metrics
+                        .consecutive_failures
+                        .with_label_values(&[chain_id.to_string().as_str()])
+                        .set(chain_health.consecutive_failures as i64);
+                    let backoff = ERROR_INTERVAL
+                        .saturating_mul(1 << (chain_health.consecutive_failures - 1).min(16))
+                        .min(loop_interval);
+                    chain_health.retry_at = Some(now + backoff);
+                    error!(
+                        "Chain {} failed ({} consecutive failures), retrying in {} seconds: {:?}",
+                        chain_id,
+                        chain_health.consecutive_failures,
+                        backoff.as_secs(),
+                        e
+                    );
+                    chain_health.last_error = Some(format!("{:#}", e));
+                }
+                None => {
+                    chain_health.consecutive_failures = 0;
+                    self.metrics
+                        .consecutive_failures
+                        .with_label_values(&[chain_id.to_string().as_str()])
+                        .set(0);
+                    chain_health.last_error = None;
+                    chain_health.retry_at = None;
+                }
+            }
         }
+    }
 
-        Ok(())
+    /// Log the health of every chain.
+    fn report_health(&self) {
+        let health = self.health.lock().unwrap();
+        let failing: Vec<_> = health
+            .iter()
+            .filter(|(_, h)| h.consecutive_failures > 0)
+            .collect();
+
+        info!(
+            "Chain health: {} healthy, {} failing",
+            self.contracts.len() - failing.len(),
+            failing.len()
+        );
+        for (chain_id, h) in failing {
+            warn!(
+                "Chain {} is failing: {} consecutive failures, last error: {}",
+                chain_id,
+                h.consecutive_failures,
+                h.last_error.as_deref().unwrap_or_default()
+            );
+        }
     }
 
     // Run the operator, indefinitely.
     async fn run(self) {
-        let loop_interval = Duration::from_secs(get_loop_interval_mins() * 60);
-        let error_interval = Duration::from_secs(10);
+        let loop_interval = Duration::from_secs(self.config.loop_interval_mins * 60);
+        let loop_timeout = Duration::from_secs(self.config.loop_timeout_mins * 60);
+
+        if let Some(metrics_addr) = self.config.metrics_addr {
+            let metrics = self.metrics.clone();
+            tokio::spawn(async move {
+                if let Err(e) = metrics.serve(metrics_addr).await {
+                    error!("Metrics server failed: {:?}", e);
+                }
+            });
+        }
 
+        // The next run of the operator loop, shared by the chains that are not failing.
+        let mut next_loop = Instant::now();
         loop {
-            tokio::select! {
-                res = self.run_once() => {
-                    if let Err(e) = res {
-                        error!("Error during `run_once`: {:?}", e);
-                        // Sleep for less time if theres an error.
-                        tokio::time::sleep(error_interval).await;
-                        continue;
-                    }
-                },
-                _ = tokio::time::sleep(Duration::from_secs(LOOP_TIMEOUT_MINS * 60)) => {
-                    continue;
-                }
+            let loop_due = next_loop <= Instant::now();
+            let chain_ids = self.due_chains(loop_due);
+
+            if !chain_ids.is_empty() {
+                let failures = tokio::select! {
+                    failures = self.run_once(&chain_ids) => failures,
+                    _ = tokio::time::sleep(loop_timeout) => chain_ids
+                        .iter()
+                        .map(|&id| (id, anyhow::anyhow!("Operator loop timed out")))
+                        .collect(),
+                };
+                self.update_health(&chain_ids, failures);
+                self.report_health();
+            }
+            if loop_due {
+                next_loop = Instant::now() + loop_interval;
             }
 
-            tracing::info!(
-                "Operator ran successfully, sleeping for {} seconds",
-                loop_interval.as_secs()
-            );
+            // Sleep until the next loop, or the next retry of a failing chain.
+            let now = Instant::now();
+            let sleep = self
+                .health
+                .lock()
+                .unwrap()
+                .values()
+                .filter_map(|h| h.retry_at)
+                .fold(next_loop, Instant::min)
+                .saturating_duration_since(now);
 
-            // Sleep for the loop interval.
-            tokio::time::sleep(loop_interval).await;
+            tracing::info!("Operator sleeping for {} seconds", sleep.as_secs());
+
+            tokio::time::sleep(sleep).await;
         }
     }
 }
 
-fn get_loop_interval_mins() -> u64 {
-    let loop_interval_mins_env = env::var("LOOP_INTERVAL_MINS");
-    let mut loop_interval_mins = 60;
-    if loop_interval_mins_env.is_ok() {
-        loop_interval_mins = loop_interval_mins_env
-            .unwrap()
-            .parse::<u64>()
-            .expect("invalid LOOP_INTERVAL_MINS");
-    }
-    loop_interval_mins
+////////////////////////////////////////////////////////////
+// Dry Run
+////////////////////////////////////////////////////////////
+
+/// An update the operator would make to a batch of chains.
+#[derive(Debug, Clone, Copy)]
+enum PlannedUpdate {
+    Rotate {
+        authority_set_id: u64,
+        proof_system: ProofSystem,
+    },
+    HeaderRange(HeaderRangeProofKey),
 }
 
-fn get_block_update_interval() -> u32 {
-    let block_update_interval_env = env::var("BLOCK_UPDATE_INTERVAL");
-    let mut block_update_interval = 360;
-    if block_update_interval_env.is_ok() {
-        block_update_interval = block_update_interval_env
-            .unwrap()
-            .parse::<u32>()
-            .expect("invalid BLOCK_UPDATE_INTERVAL");
+impl PlannedUpdate {
+    fn proof_key(&self) -> ProofKey {
+        match self {
+            PlannedUpdate::Rotate {
+                authority_set_id, ..
+            } => ProofKey::Rotate {
+                authority_set_id: *authority_set_id,
+            },
+            PlannedUpdate::HeaderRange(key) => ProofKey::HeaderRange {
+                trusted_block: key.request.trusted_block,
+                target_block: key.request.target_block,
+                tree_size: key.tree_size,
+            },
+        }
     }
-    block_update_interval
-}
 
-#[tokio::main]
-async fn main() {
-    dotenv::dotenv().ok();
-    tracing_subscriber::fmt::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::from_env("info")),
-        )
-        .init();
+    fn proof_system(&self) -> ProofSystem {
+        match self {
+            PlannedUpdate::Rotate { proof_system, .. } => *proof_system,
+            PlannedUpdate::HeaderRange(key) => key.proof_system,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            PlannedUpdate::Rotate {
+                authority_set_id,
+                proof_system,
+            } => format!(
+                "Rotate to authority set {} with a {:?} proof.",
+                authority_set_id + 1,
+                proof_system
+            ),
+            PlannedUpdate::HeaderRange(key) => format!(
+                "Commit header range from block {} to block {} (tree size {}) with a {:?} proof.",
+                key.request.trusted_block,
+                key.request.target_block,
+                key.tree_size,
+                key.proof_system
+            ),
+        }
+    }
+}
 
-    let signer_mode = env::var("SIGNER_MODE")
-        .map(|v| v.parse().unwrap())
-        .unwrap_or(SignerMode::Local);
-    let config = ChainConfig::fetch().expect("Failed to fetch chain config");
+/// Describe the contract state changes committed to by the public values of a proof.
+fn describe_state_changes(public_values: &[u8]) -> Result<Vec<String>> {
+    let (proof_type, header_range_outputs, rotate_outputs) =
+        ProofOutput::abi_decode(public_values)?;
+
+    match ProofType::from_uint(proof_type) {
+        Some(ProofType::HeaderRangeProof) => {
+            let (
+                trusted_block,
+                _,
+                _,
+                _,
+                target_block,
+                target_header_hash,
+                state_root_commitment,
+                data_root_commitment,
+                _,
+            ) = HeaderRangeOutputs::abi_decode(&header_range_outputs)?;
+            let range_hash = keccak256((trusted_block, target_block).abi_encode_params());
+
+            Ok(vec![
+                format!("latestBlock: {} -> {}", trusted_block, target_block),
+                format!(
+                    "blockHeightToHeaderHash[{}] = {}",
+                    target_block, target_header_hash
+                ),
+                format!(
+                    "dataRootCommitments[{}] = {}",
+                    range_hash, data_root_commitment
+                ),
+                format!(
+                    "stateRootCommitments[{}] = {}",
+                    range_hash, state_root_commitment
+                ),
+            ])
+        }
+        Some(ProofType::RotateProof) => {
+            let (current_authority_set_id, _, new_authority_set_hash) =
+                RotateOutputs::abi_decode(&rotate_outputs)?;
+
+            Ok(vec![format!(
+                "authoritySetIdToHash[{}] = {}",
+                current_authority_set_id + 1,
+                new_authority_set_hash
+            )])
+        }
+        None => Err(anyhow::anyhow!("Invalid proof type {}", proof_type)),
+    }
+}
 
-    match signer_mode {
-        SignerMode::Local => run_with_signer(config).await,
-        SignerMode::Kms => run_with_kms(config).await,
+/// Describe why a simulated transaction failed, decoding the contract's custom errors.
+fn describe_revert(error: &TransportError) -> String {
+    match error.as_error_resp().and_then(|e| e.as_revert_data()) {
+        Some(data) => match SP1Vector::SP1VectorErrors::abi_decode(&data) {
+            Ok(error) => format!("{:?}", error),
+            Err(_) => format!("revert data {}", data),
+        },
+        None => format!("{:#}", error),
     }
 }
 
-async fn run_with_signer(config: Vec<ChainConfig>) {
-    let mut operator = SP1VectorOperator::new(SignerMode::Local).await;
+impl<P, N> SP1VectorOperator<P, N>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    /// Print what a single iteration of the operator would do on each chain, without proving or
+    /// sending transactions.
+    ///
+    /// The inputs of each planned proof are built, and the relay is simulated with `eth_call` from
+    /// `from`. The public values come from a stored proof if there is one, otherwise from executing
+    /// the program locally if `execute` is set, with an empty proof that only passes mock verifiers.
+    async fn dry_run(&self, execute: bool, from: Option<Address>) {
+        let rpc_timeout = Duration::from_secs(self.config.rpc_timeout_secs);
+        let mut chain_ids: Vec<u64> = self.contracts.keys().copied().collect();
+        chain_ids.sort_unstable();
+
+        let mut reports: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+        let mut rotates: HashMap<(u64, ProofSystem), Vec<u64>> = HashMap::new();
+        let mut requests = Vec::new();
+        for &chain_id in &chain_ids {
+            let report = reports.entry(chain_id).or_default();
+
+            let vkey_result = self
+                .check_vkey(chain_id)
+                .timeout(rpc_timeout)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            if let Err(e) = vkey_result {
+                report.push(format!("Skipped: {:#}", e));
+                continue;
+            }
+
+            let proof_system = self.chain_settings[&chain_id].proof_system;
+            let rotate = self
+                .find_rotate(chain_id)
+                .timeout(rpc_timeout)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            match rotate {
+                Ok(Some(authority_set_id)) => rotates
+                    .entry((authority_set_id, proof_system))
+                    .or_default()
+                    .push(chain_id),
+                Ok(None) => report.push("No rotate needed.".to_string()),
+                Err(e) => report.push(format!("Failed to find rotate: {:#}", e)),
+            }
 
-    let signer: PrivateKeySigner = env::var("PRIVATE_KEY")
-        .expect("PRIVATE_KEY must be set")
-        .parse()
-        .expect("Failed to parse PRIVATE_KEY");
+            let block_interval = self.chain_settings[&chain_id].block_update_interval;
+            let header_range = self
+                .find_header_range(chain_id, block_interval)
+                .timeout(rpc_timeout)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            match header_range {
+                Ok(Some((request, tree_size))) => requests.push(ChainRequest {
+                    chain_id,
+                    request,
+                    tree_size,
+                }),
+                Ok(None) => report.push("No header range update needed.".to_string()),
+                Err(e) => report.push(format!("Failed to find header range: {:#}", e)),
+            }
+        }
+
+        let mut updates: Vec<(PlannedUpdate, Vec<u64>)> = rotates
+            .into_iter()
+            .map(|((authority_set_id, proof_system), chain_ids)| {
+                (
+                    PlannedUpdate::Rotate {
+                        authority_set_id,
+                        proof_system,
+                    },
+                    chain_ids,
+                )
+            })
+            .collect();
+
+        let mut header_ranges: HashMap<HeaderRangeProofKey, Vec<u64>> = HashMap::new();
+        for request in self.align_header_range_requests(&requests) {
+            header_ranges
+                .entry(HeaderRangeProofKey {
+                    request: request.request,
+                    tree_size: request.tree_size,
+                    proof_system: self.chain_settings[&request.chain_id].proof_system,
+                })
+                .or_default()
+                .push(request.chain_id);
+        }
+        updates.extend(
+            header_ranges
+                .into_iter()
+                .map(|(key, chain_ids)| (PlannedUpdate::HeaderRange(key), chain_ids)),
+        );
 
-    for c in config {
-        let provider = ProviderBuilder::new()
-            .wallet(signer.clone())
-            .on_http(c.rpc_url.parse().expect("Failed to parse RPC URL"));
+        for (update, chain_ids) in updates {
+            let proof = self.dry_run_proof(&update, execute).await;
 
-        operator = operator.with_chain(provider, c.vector_address).await;
-    }
+            for chain_id in chain_ids {
+                let report = reports.entry(chain_id).or_default();
+                report.push(update.describe());
 
-    operator.run().await
-}
+                let (proof, public_values) = match &proof {
+                    Ok(Some(proof)) => proof.clone(),
+                    Ok(None) => {
+                        report.push(
+                            "  Not executed, pass --execute to simulate the transaction."
+                                .to_string(),
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        report.push(format!("  Failed to build the proof: {:#}", e));
+                        continue;
+                    }
+                };
 
-async fn run_with_kms(config: Vec<ChainConfig>) {
-    let mut operator = SP1VectorOperator::new(SignerMode::Kms).await;
+                match describe_state_changes(&public_values) {
+                    Ok(changes) => {
+                        report.extend(changes.into_iter().map(|change| format!("  {}", change)))
+                    }
+                    Err(e) => report.push(format!("  Failed to decode public values: {:#}", e)),
+                }
 
-    for c in config {
-        let provider =
-            ProviderBuilder::new().on_http(c.rpc_url.parse().expect("Failed to parse RPC URL"));
+                match self
+                    .simulate(chain_id, &update, proof, public_values, from)
+                    .await
+                {
+                    Ok(()) => report.push("  Simulation succeeded.".to_string()),
+                    Err(e) => report.push(format!("  Simulation failed: {}", e)),
+                }
+            }
+        }
 
-        operator = operator.with_chain(provider, c.vector_address).await;
+        for (chain_id, report) in reports {
+            println!("Chain {}:", chain_id);
+            for line in report {
+                println!("  {}", line);
+            }
+        }
     }
 
-    operator.run().await
-}
+    /// Build the inputs of a planned update, and get the proof bytes and public values to simulate
+    /// it with. Returns None if there is no stored proof and `execute` is not set.
+    async fn dry_run_proof(
+        &self,
+        update: &PlannedUpdate,
+        execute: bool,
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let inputs = match update {
+            PlannedUpdate::Rotate {
+                authority_set_id, ..
+            } => ProofInputs::Rotate(self.fetcher.get_rotate_inputs(*authority_set_id).await?),
+            PlannedUpdate::HeaderRange(key) => ProofInputs::HeaderRange(
+                self.fetcher
+                    .get_header_range_inputs(key.request, Some(key.tree_size))
+                    .await?,
+            ),
+        };
+
+        if let Some(proof) = self.get_stored_proof(&update.proof_key(), update.proof_system()) {
+            return Ok(Some((proof.bytes(), proof.public_values.to_vec())));
+        }
+        if !execute {
+            return Ok(None);
+        }
 
-mod config {
-    use alloy::primitives::Address;
-    use anyhow::{Context, Result};
-    use std::{env, str::FromStr};
+        let client = ProverClient::builder().mock().build();
+        let (public_values, report) = client.execute(SP1_VECTOR_ELF, &inputs.to_stdin()).run()?;
+        debug!(
+            "Executed {:?} in {} instructions",
+            update,
+            report.total_instruction_count()
+        );
 
-    #[derive(Debug)]
-    pub enum SignerMode {
-        Kms,
-        Local,
+        Ok(Some((Vec::new(), public_values.to_vec())))
     }
 
-    impl FromStr for SignerMode {
-        type Err = anyhow::Error;
+    /// Simulate relaying a planned update to the given chain with `eth_call`.
+    async fn simulate(
+        &self,
+        chain_id: u64,
+        update: &PlannedUpdate,
+        proof: Vec<u8>,
+        public_values: Vec<u8>,
+        from: Option<Address>,
+    ) -> Result<(), String> {
+        let contract = self
+            .contracts
+            .get(&chain_id)
+            .expect("No contract for chain id");
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            Ok(match s {
-                "kms" => Self::Kms,
-                "local" => Self::Local,
-                _ => return Err(anyhow::anyhow!("Invalid signer mode: {}", s)),
-            })
+        let mut tx = match update {
+            PlannedUpdate::Rotate { .. } => contract
+                .rotate(proof.into(), public_values.into())
+                .into_transaction_request(),
+            PlannedUpdate::HeaderRange(_) => contract
+                .commitHeaderRange(proof.into(), public_values.into())
+                .into_transaction_request(),
+        };
+        if let Some(from) = from {
+            tx.set_from(from);
         }
-    }
 
-    #[derive(Debug, serde::Deserialize)]
-    pub struct ChainConfig {
-        pub rpc_url: String,
-        pub vector_address: Address,
+        contract
+            .provider()
+            .call(tx)
+            .await
+            .map(|_| ())
+            .map_err(|e| describe_revert(&e))
     }
+}
 
-    impl ChainConfig {
-        /// Tries to read from the `CHAINS_PATH` environment variable, then the default path (`../chains.json`).
-        ///
-        /// If neither are set, it will try to use [`Self::from_env`].
-        pub fn fetch() -> Result<Vec<Self>> {
-            const DEFAULT_PATH: &str = "chains.json";
+#[derive(Parser, Debug)]
+#[command(about = "Prove and relay SP1 Vector updates to the configured chains.")]
+struct OperatorArgs {
+    /// The operator config file, TOML or JSON. Defaults to `operator.toml` if it exists, otherwise
+    /// the config is read from the environment.
+    #[arg(long, env = "OPERATOR_CONFIG")]
+    config: Option<String>,
+    /// Print the effective config, with the RPC URLs redacted, and exit.
+    #[arg(long)]
+    print_config: bool,
+    /// Print the updates the operator would make on each chain and simulate them, without proving
+    /// or sending transactions.
+    #[arg(long)]
+    dry_run: bool,
+    /// With `--dry-run`, execute the program locally to compute the expected state changes and
+    /// simulate the transactions, if there is no stored proof.
+    #[arg(long, requires = "dry_run")]
+    execute: bool,
+    /// With `--dry-run`, the sender of the simulated transactions. Defaults to the address of
+    /// `PRIVATE_KEY` if it is set.
+    #[arg(long, requires = "dry_run")]
+    from: Option<Address>,
+}
 
-            let path = env::var("CHAINS_PATH").unwrap_or(DEFAULT_PATH.to_string());
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    tracing_subscriber::fmt::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::from_env("info")),
+        )
+        .init();
 
-            Self::from_file(&path).or_else(|_| {
-                tracing::info!("No chains file found, trying env.");
-                Self::from_env().map(|c| vec![c])
-            })
+    let args = OperatorArgs::parse();
+    let config = match OperatorConfig::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid operator config: {:#}", e);
+            std::process::exit(1);
         }
+    };
 
-        /// Tries to read from the `CONTRACT_ADDRESS` and `RPC_URL` environment variables.
-        pub fn from_env() -> Result<Self> {
-            let address = env::var("CONTRACT_ADDRESS").context("CONTRACT_ADDRESS not set")?;
-            let rpc_url = env::var("RPC_URL").context("RPC_URL not set")?;
-
-            Ok(Self {
-                rpc_url,
-                vector_address: address.parse()?,
-            })
-        }
+    if args.print_config {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&config).expect("Failed to serialize config")
+        );
+        return;
+    }
 
-        pub fn from_file(path: &str) -> Result<Vec<Self>> {
-            tracing::debug!("Reading chains from file: {}", path);
+    if args.dry_run {
+        // A dry run never signs, so the private key is only used as the default sender.
+        let signer: Option<PrivateKeySigner> = env::var("PRIVATE_KEY")
+            .ok()
+            .map(|key| key.parse().expect("Failed to parse PRIVATE_KEY"));
+        let from = args.from.or(signer.map(|s| s.address()));
+        let operator = build_operator(config, None).await;
+        operator.dry_run(args.execute, from).await;
+        return;
+    }
 
-            let file = std::fs::read_to_string(path)?;
+    let signer: Option<PrivateKeySigner> = config.uses_local_signer().then(|| {
+        env::var("PRIVATE_KEY")
+            .expect("PRIVATE_KEY must be set")
+            .parse()
+            .expect("Failed to parse PRIVATE_KEY")
+    });
+    build_operator(config, signer).await.run().await
+}
 
-            Ok(serde_json::from_str(&file)?)
-        }
+/// Build the operator, with a provider for each chain of the config. Chains using the local signer
+/// sign with `signer`, or are read-only if it is not set.
+async fn build_operator(
+    config: OperatorConfig,
+    signer: Option<PrivateKeySigner>,
+) -> SP1VectorOperator<DynProvider, Ethereum> {
+    let chains = config.chains.clone();
+    let mut operator = SP1VectorOperator::<DynProvider, Ethereum>::new(config).await;
+    operator.signer_address = signer.as_ref().map(|signer| signer.address());
+
+    for c in chains {
+        let rpc_url = c.rpc_url.parse().expect("Failed to parse RPC URL");
+        let provider = match (c.settings.signer_mode, &signer) {
+            (SignerMode::Local, Some(signer)) => ProviderBuilder::new()
+                .wallet(signer.clone())
+                .on_http(rpc_url)
+                .erased(),
+            _ => ProviderBuilder::new().on_http(rpc_url).erased(),
+        };
+
+        operator = operator
+            .with_chain(provider, c.vector_address, c.settings)
+            .await;
     }
+
+    operator
 }
@@ -0,0 +1,304 @@
+//! The fee and gas limit policy of the transactions relayed to a chain, from its [`GasPolicy`].
+//!
+//! Fees are estimated from the next block's base fee and the node's priority fee, then capped by the
+//! policy. Transactions are held back while the base fee is above the policy's limit.
+
+use std::time::{Duration, Instant};
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::{Network, TransactionBuilder};
+use alloy::primitives::{address, Address, Bytes, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::config::{BaseFeeLimitAction, ChainKind, GasPolicy};
+use crate::pending_tx::Fees;
+
+/// The default margin of the gas limit over the gas estimate.
+pub const DEFAULT_GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
+
+/// The default margin of the gas limit on Arbitrum, whose gas estimate includes the L1 data cost
+/// and moves with the L1 gas price.
+pub const DEFAULT_ARBITRUM_GAS_LIMIT_MARGIN_PERCENT: u64 = 50;
+
+/// The default margin of the max fee per gas over the next base fee. The base fee can rise by
+/// 12.5% per block, so this covers several full blocks.
+pub const DEFAULT_BASE_FEE_MARGIN_PERCENT: u64 = 100;
+
+/// The default time to wait for the base fee to drop below the limit.
+pub const DEFAULT_BASE_FEE_WAIT_SECS: u64 = 600;
+
+/// How often to check the base fee while waiting for it to drop.
+const BASE_FEE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The OP-stack predeploy that prices the L1 data fee.
+const OP_GAS_PRICE_ORACLE: Address = address!("420000000000000000000000000000000000000F");
+
+sol! {
+    #[sol(rpc)]
+    interface IGasPriceOracle {
+        function getL1Fee(bytes memory data) external view returns (uint256);
+    }
+}
+
+impl ChainKind {
+    /// The fee model of the known Arbitrum and OP-stack chains, and Ethereum otherwise.
+    pub fn from_chain_id(chain_id: u64) -> Self {
+        match chain_id {
+            // Arbitrum One, Arbitrum Nova and Arbitrum Sepolia.
+            42161 | 42170 | 421614 => ChainKind::Arbitrum,
+            // OP Mainnet, OP Sepolia, Base and Base Sepolia.
+            10 | 11155420 | 8453 | 84532 => ChainKind::Optimism,
+            _ => ChainKind::Ethereum,
+        }
+    }
+}
+
+/// The fee policy of a chain, with the defaults of its fee model filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePolicy {
+    pub chain_kind: ChainKind,
+    pub gas: GasPolicy,
+}
+
+impl FeePolicy {
+    pub fn new(chain_id: u64, gas: GasPolicy) -> Self {
+        Self {
+            chain_kind: gas
+                .chain_kind
+                .unwrap_or_else(|| ChainKind::from_chain_id(chain_id)),
+            gas,
+        }
+    }
+
+    /// The gas limit of a transaction with the given gas estimate.
+    pub fn gas_limit(&self, estimate: u64) -> u64 {
+        if let Some(gas_limit) = self.gas.gas_limit {
+            return gas_limit;
+        }
+
+        let margin = self
+            .gas
+            .gas_limit_margin_percent
+            .unwrap_or(match self.chain_kind {
+                ChainKind::Arbitrum => DEFAULT_ARBITRUM_GAS_LIMIT_MARGIN_PERCENT,
+                _ => DEFAULT_GAS_LIMIT_MARGIN_PERCENT,
+            });
+        estimate + estimate * margin / 100
+    }
+
+    /// The fees of a transaction, from the next base fee and the node's priority fee. The max fee
+    /// covers the capped priority fee.
+    pub fn fees(&self, base_fee: u128, priority_fee: u128) -> Fees {
+        // The Arbitrum sequencer orders transactions first come, first served, and ignores the
+        // priority fee.
+        let priority_fee = match self.chain_kind {
+            ChainKind::Arbitrum => 0,
+            _ => self
+                .gas
+                .max_priority_fee_per_gas
+                .map_or(priority_fee, |cap| priority_fee.min(cap as u128)),
+        };
+        let margin = self
+            .gas
+            .base_fee_margin_percent
+            .unwrap_or(DEFAULT_BASE_FEE_MARGIN_PERCENT) as u128;
+
+        self.cap(Fees {
+            max_fee_per_gas: base_fee + base_fee * margin / 100 + priority_fee,
+            max_priority_fee_per_gas: priority_fee,
+        })
+    }
+
+    /// Cap the fees at the policy's max fees.
+    pub fn cap(&self, fees: Fees) -> Fees {
+        let max_fee_per_gas = self
+            .gas
+            .max_fee_per_gas
+            .map_or(fees.max_fee_per_gas, |cap| {
+                fees.max_fee_per_gas.min(cap as u128)
+            });
+        let max_priority_fee_per_gas = self
+            .gas
+            .max_priority_fee_per_gas
+            .map_or(fees.max_priority_fee_per_gas, |cap| {
+                fees.max_priority_fee_per_gas.min(cap as u128)
+            })
+            .min(max_fee_per_gas);
+
+        Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    }
+
+    /// Whether a transaction can be sent at the given base fee.
+    pub fn is_base_fee_allowed(&self, base_fee: u128) -> bool {
+        self.gas
+            .max_base_fee_per_gas
+            .map_or(true, |limit| base_fee <= limit as u128)
+    }
+
+    /// Get the next base fee of the chain, waiting for it to drop below the policy's limit.
+    ///
+    /// Returns an error if the base fee is above the limit and the policy skips, or if it is still
+    /// above the limit after `base_fee_wait_secs`.
+    pub async fn wait_for_base_fee<P, N>(&self, provider: &P) -> Result<u128>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        let wait = Duration::from_secs(
+            self.gas
+                .base_fee_wait_secs
+                .unwrap_or(DEFAULT_BASE_FEE_WAIT_SECS),
+        );
+        let start = Instant::now();
+
+        loop {
+            let base_fee = next_base_fee(provider).await?;
+            if self.is_base_fee_allowed(base_fee) {
+                return Ok(base_fee);
+            }
+
+            let limit = self.gas.max_base_fee_per_gas.unwrap_or_default();
+            if self.gas.base_fee_limit_action.unwrap_or_default() == BaseFeeLimitAction::Skip
+                || start.elapsed() >= wait
+            {
+                return Err(anyhow::anyhow!(
+                    "Base fee {} is above the limit of {}, skipping",
+                    base_fee,
+                    limit
+                ));
+            }
+
+            warn!(
+                "Base fee {} is above the limit of {}, waiting for it to drop",
+                base_fee, limit
+            );
+            tokio::time::sleep(BASE_FEE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Estimate the gas limit and fees of a transaction from the signer. On OP-stack chains, the L1
+    /// data fee paid on top of the fees is logged.
+    pub async fn estimate<P, N>(&self, provider: &P, tx: &N::TransactionRequest) -> Result<TxFees>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        let base_fee = self.wait_for_base_fee(provider).await?;
+        let priority_fee = provider.get_max_priority_fee_per_gas().await?;
+        let fees = self.fees(base_fee, priority_fee);
+
+        let gas_limit = match self.gas.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => self.gas_limit(provider.estimate_gas(tx.clone()).await?),
+        };
+
+        if self.chain_kind == ChainKind::Optimism {
+            if let Some(input) = tx.input() {
+                let l1_fee = l1_data_fee(provider, input.clone()).await?;
+                info!(
+                    "Estimated L1 data fee of {} wei on top of the max execution fee of {} wei",
+                    l1_fee,
+                    gas_limit as u128 * fees.max_fee_per_gas
+                );
+            }
+        }
+
+        Ok(TxFees { gas_limit, fees })
+    }
+}
+
+/// The gas limit and fees of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxFees {
+    pub gas_limit: u64,
+    pub fees: Fees,
+}
+
+/// The base fee of the next block of the chain.
+pub async fn next_base_fee<P, N>(provider: &P) -> Result<u128>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    let fee_history = provider
+        .get_fee_history(1, BlockNumberOrTag::Latest, &[])
+        .await?;
+
+    fee_history
+        .next_block_base_fee()
+        .ok_or_else(|| anyhow::anyhow!("No base fee in the fee history"))
+}
+
+/// The L1 data fee an OP-stack chain charges for the calldata of a transaction.
+async fn l1_data_fee<P, N>(provider: &P, calldata: Bytes) -> Result<U256>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    let oracle = IGasPriceOracle::new::<_, N>(OP_GAS_PRICE_ORACLE, provider);
+
+    Ok(oracle.getL1Fee(calldata).call().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_policy() {
+        let policy = FeePolicy::new(
+            1,
+            GasPolicy {
+                max_fee_per_gas: Some(250),
+                max_priority_fee_per_gas: Some(10),
+                max_base_fee_per_gas: Some(200),
+                ..Default::default()
+            },
+        );
+        assert_eq!(policy.chain_kind, ChainKind::Ethereum);
+        assert_eq!(policy.gas_limit(1_000_000), 1_200_000);
+
+        // The max fee is twice the base fee plus the priority fee, and the priority fee is capped.
+        assert_eq!(
+            policy.fees(100, 20),
+            Fees {
+                max_fee_per_gas: 210,
+                max_priority_fee_per_gas: 10,
+            }
+        );
+        // The max fee is capped.
+        assert_eq!(policy.fees(150, 5).max_fee_per_gas, 250);
+
+        assert!(policy.is_base_fee_allowed(200));
+        assert!(!policy.is_base_fee_allowed(201));
+    }
+
+    #[test]
+    fn test_arbitrum_fee_policy() {
+        let policy = FeePolicy::new(42161, GasPolicy::default());
+        assert_eq!(policy.chain_kind, ChainKind::Arbitrum);
+        assert_eq!(policy.gas_limit(1_000_000), 1_500_000);
+        assert_eq!(
+            policy.fees(100, 20),
+            Fees {
+                max_fee_per_gas: 200,
+                max_priority_fee_per_gas: 0,
+            }
+        );
+
+        let fixed = FeePolicy::new(
+            42161,
+            GasPolicy {
+                gas_limit: Some(25_000_000),
+                ..Default::default()
+            },
+        );
+        assert_eq!(fixed.gas_limit(1_000_000), 25_000_000);
+    }
+}
@@ -0,0 +1,196 @@
+//! Portable files containing the exact inputs to an SP1 Vector proof. A proof can be reproduced from
+//! these files without access to the Avail RPC or the justification indexer.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::SP1Stdin;
+use sp1_vector_primitives::types::{HeaderRangeInputs, ProofType, RotateInputs};
+
+/// The current version of the proof inputs file format.
+pub const PROOF_INPUTS_VERSION: u32 = 1;
+
+/// The magic bytes at the start of a bincode proof inputs file, followed by the little endian
+/// version.
+const PROOF_INPUTS_MAGIC: &[u8; 8] = b"SP1VINPT";
+
+/// The inputs to an SP1 Vector proof. The variant determines the `ProofType` written to the program.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProofInputs {
+    /// Inputs to a header range proof. The commitment tree size is the `merkle_tree_size`.
+    HeaderRange(HeaderRangeInputs),
+    /// Inputs to a rotate proof.
+    Rotate(RotateInputs),
+}
+
+impl ProofInputs {
+    pub fn proof_type(&self) -> ProofType {
+        match self {
+            ProofInputs::HeaderRange(_) => ProofType::HeaderRangeProof,
+            ProofInputs::Rotate(_) => ProofType::RotateProof,
+        }
+    }
+
+    /// Write the proof type and inputs to the stdin of the SP1 Vector program.
+    pub fn to_stdin(&self) -> SP1Stdin {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&self.proof_type());
+        match self {
+            ProofInputs::HeaderRange(header_range_inputs) => stdin.write(header_range_inputs),
+            ProofInputs::Rotate(rotate_inputs) => stdin.write(rotate_inputs),
+        }
+        stdin
+    }
+}
+
+/// A versioned file containing the inputs to an SP1 Vector proof.
+///
+/// Files with a `.json` extension are encoded as JSON, all other files are encoded with bincode. The
+/// version is read before the inputs, so inputs of another version are rejected before they are
+/// decoded: bincode files start with a fixed header, and JSON files with the `version` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofInputsFile {
+    pub version: u32,
+    /// The Avail chain the inputs were fetched from.
+    pub avail_chain_id: String,
+    pub inputs: ProofInputs,
+}
+
+impl ProofInputsFile {
+    pub fn new(avail_chain_id: &str, inputs: ProofInputs) -> Self {
+        Self {
+            version: PROOF_INPUTS_VERSION,
+            avail_chain_id: avail_chain_id.to_string(),
+            inputs,
+        }
+    }
+
+    /// Save the proof inputs to the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, self.encode(is_json(path))?)
+            .context(format!("Failed to write {}", path.display()))
+    }
+
+    /// Load the proof inputs from the given path. Fails if the file was written with a different
+    /// version of the format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+
+        Self::decode(&bytes, is_json(path)).context(format!("Failed to decode {}", path.display()))
+    }
+
+    fn encode(&self, json: bool) -> Result<Vec<u8>> {
+        if json {
+            return Ok(serde_json::to_vec_pretty(self)?);
+        }
+
+        let mut bytes = PROOF_INPUTS_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8], json: bool) -> Result<Self> {
+        let version = if json {
+            #[derive(Deserialize)]
+            struct Version {
+                version: u32,
+            }
+            serde_json::from_slice::<Version>(bytes)?.version
+        } else {
+            let header_len = PROOF_INPUTS_MAGIC.len() + 4;
+            if bytes.len() < header_len || !bytes.starts_with(PROOF_INPUTS_MAGIC) {
+                return Err(anyhow::anyhow!("Not a proof inputs file"));
+            }
+            u32::from_le_bytes(bytes[PROOF_INPUTS_MAGIC.len()..header_len].try_into()?)
+        };
+
+        if version != PROOF_INPUTS_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported proof inputs version {}, expected {}",
+                version,
+                PROOF_INPUTS_VERSION
+            ));
+        }
+
+        if json {
+            Ok(serde_json::from_slice(bytes)?)
+        } else {
+            Ok(bincode::deserialize(
+                &bytes[PROOF_INPUTS_MAGIC.len() + 4..],
+            )?)
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+    use sp1_vector_primitives::types::{CircuitJustification, HeaderRotateData};
+
+    use super::*;
+
+    fn rotate_inputs_file() -> ProofInputsFile {
+        ProofInputsFile::new(
+            "turing",
+            ProofInputs::Rotate(RotateInputs {
+                justification: CircuitJustification {
+                    round: 7,
+                    authority_set_id: 42,
+                    valset_pubkeys: vec![B256::with_last_byte(1)],
+                    precommits: vec![],
+                    block_hash: B256::with_last_byte(2),
+                    ancestries_encoded: vec![vec![1, 2, 3]],
+                },
+                header_rotate_data: HeaderRotateData {
+                    header_bytes: vec![4, 5, 6],
+                    consensus_log_position: 1,
+                },
+            }),
+        )
+    }
+
+    #[test]
+    fn test_proof_inputs_file_round_trip() {
+        let file = rotate_inputs_file();
+
+        for json in [true, false] {
+            let bytes = file.encode(json).unwrap();
+            let decoded = ProofInputsFile::decode(&bytes, json).unwrap();
+            assert_eq!(decoded.avail_chain_id, "turing");
+            let ProofInputs::Rotate(rotate_inputs) = decoded.inputs else {
+                panic!("Expected rotate inputs");
+            };
+            assert_eq!(rotate_inputs.justification.authority_set_id, 42);
+            assert_eq!(rotate_inputs.header_rotate_data.header_bytes, vec![4, 5, 6]);
+        }
+    }
+
+    #[test]
+    fn test_proof_inputs_file_rejects_other_version() {
+        let mut file = rotate_inputs_file();
+        file.version = PROOF_INPUTS_VERSION + 1;
+
+        for json in [true, false] {
+            let bytes = file.encode(json).unwrap();
+            let error = ProofInputsFile::decode(&bytes, json).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("Unsupported proof inputs version"));
+        }
+        assert!(ProofInputsFile::decode(b"not a file", false).is_err());
+    }
+}
@@ -1,4 +1,12 @@
+pub mod config;
+pub mod fees;
+pub mod inputs;
+pub mod metrics;
+pub mod pending_tx;
+pub mod proof_store;
+pub mod prover;
 pub mod relay;
+pub mod scheduler;
 
 pub const SP1_VECTOR_ELF: &[u8] = include_bytes!("../../elf/vector-elf");
 
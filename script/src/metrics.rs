@@ -0,0 +1,190 @@
+//! Prometheus metrics of the operator, served over HTTP at `/metrics`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sp1_vector_primitives::types::ProofType;
+use tracing::info;
+
+const NAMESPACE: &str = "sp1_vector_operator";
+
+/// The metrics of the operator. Gauges and counters of a destination chain are labelled by
+/// `chain_id`.
+pub struct OperatorMetrics {
+    registry: Registry,
+    /// The latest block of each contract.
+    pub latest_block: IntGaugeVec,
+    /// The finalized head of the Avail chain.
+    pub avail_head_block: IntGauge,
+    /// The number of Avail blocks each contract is behind the finalized head.
+    pub blocks_behind_head: IntGaugeVec,
+    /// The latest authority set id of each contract.
+    pub latest_authority_set_id: IntGaugeVec,
+    /// The authority set id of the Avail finalized head.
+    pub avail_authority_set_id: IntGauge,
+    /// The number of authority sets each contract is behind the Avail finalized head.
+    pub authority_sets_behind_head: IntGaugeVec,
+    /// The duration of successful proofs, by `proof_type`.
+    pub proof_duration_seconds: HistogramVec,
+    /// The number of failed proofs, by `proof_type`.
+    pub proof_failures: IntCounterVec,
+    /// The number of relayed transactions, by `chain_id` and `status`. The status is the
+    /// `KMSRelayStatus` of KMS relays, or the receipt status of local relays.
+    pub relays: IntCounterVec,
+    /// The gas used by the transactions relayed with the local signer.
+    pub gas_used: IntCounterVec,
+    /// The fees paid for the transactions relayed with the local signer, in gwei.
+    pub fees_paid_gwei: IntCounterVec,
+    /// The number of consecutive iterations each chain has failed.
+    pub consecutive_failures: IntGaugeVec,
+}
+
+impl OperatorMetrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new();
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE);
+
+        let metrics = Self {
+            latest_block: IntGaugeVec::new(
+                opts("latest_block", "The latest block of the contract."),
+                &["chain_id"],
+            )?,
+            avail_head_block: IntGauge::with_opts(opts(
+                "avail_head_block",
+                "The finalized head of the Avail chain.",
+            ))?,
+            blocks_behind_head: IntGaugeVec::new(
+                opts(
+                    "blocks_behind_head",
+                    "The number of Avail blocks the contract is behind the finalized head.",
+                ),
+                &["chain_id"],
+            )?,
+            latest_authority_set_id: IntGaugeVec::new(
+                opts(
+                    "latest_authority_set_id",
+                    "The latest authority set id of the contract.",
+                ),
+                &["chain_id"],
+            )?,
+            avail_authority_set_id: IntGauge::with_opts(opts(
+                "avail_authority_set_id",
+                "The authority set id of the Avail finalized head.",
+            ))?,
+            authority_sets_behind_head: IntGaugeVec::new(
+                opts(
+                    "authority_sets_behind_head",
+                    "The number of authority sets the contract is behind the Avail finalized head.",
+                ),
+                &["chain_id"],
+            )?,
+            proof_duration_seconds: HistogramVec::new(
+                HistogramOpts::new(
+                    "proof_duration_seconds",
+                    "The duration of successful proofs.",
+                )
+                .namespace(NAMESPACE)
+                .buckets(vec![
+                    30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1200.0, 1800.0, 3600.0,
+                ]),
+                &["proof_type"],
+            )?,
+            proof_failures: IntCounterVec::new(
+                opts("proof_failures_total", "The number of failed proofs."),
+                &["proof_type"],
+            )?,
+            relays: IntCounterVec::new(
+                opts("relays_total", "The number of relayed transactions."),
+                &["chain_id", "status"],
+            )?,
+            gas_used: IntCounterVec::new(
+                opts(
+                    "gas_used_total",
+                    "The gas used by the transactions relayed with the local signer.",
+                ),
+                &["chain_id"],
+            )?,
+            fees_paid_gwei: IntCounterVec::new(
+                opts(
+                    "fees_paid_gwei_total",
+                    "The fees paid for the transactions relayed with the local signer, in gwei.",
+                ),
+                &["chain_id"],
+            )?,
+            consecutive_failures: IntGaugeVec::new(
+                opts(
+                    "consecutive_failures",
+                    "The number of consecutive iterations the chain has failed.",
+                ),
+                &["chain_id"],
+            )?,
+            registry,
+        };
+
+        let collectors: Vec<Box<dyn Collector>> = vec![
+            Box::new(metrics.latest_block.clone()),
+            Box::new(metrics.avail_head_block.clone()),
+            Box::new(metrics.blocks_behind_head.clone()),
+            Box::new(metrics.latest_authority_set_id.clone()),
+            Box::new(metrics.avail_authority_set_id.clone()),
+            Box::new(metrics.authority_sets_behind_head.clone()),
+            Box::new(metrics.proof_duration_seconds.clone()),
+            Box::new(metrics.proof_failures.clone()),
+            Box::new(metrics.relays.clone()),
+            Box::new(metrics.gas_used.clone()),
+            Box::new(metrics.fees_paid_gwei.clone()),
+            Box::new(metrics.consecutive_failures.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector)?;
+        }
+
+        Ok(metrics)
+    }
+
+    /// Encode the metrics in the Prometheus text format.
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Serve the metrics at `/metrics` on the given address, forever.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> Result<()> {
+        let app = Router::new()
+            .route("/metrics", get(metrics))
+            .with_state(self);
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!("Serving metrics on {}", listener.local_addr()?);
+        axum::serve(listener, app).await?;
+
+        Ok(())
+    }
+}
+
+async fn metrics(State(metrics): State<Arc<OperatorMetrics>>) -> (StatusCode, String) {
+    match metrics.encode() {
+        Ok(body) => (StatusCode::OK, body),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// The `proof_type` label of a proof.
+pub fn proof_type_label(proof_type: &ProofType) -> &'static str {
+    match proof_type {
+        ProofType::HeaderRangeProof => "header_range",
+        ProofType::RotateProof => "rotate",
+    }
+}
@@ -0,0 +1,159 @@
+//! Tracking of the transactions relayed with the local signer. A transaction that is not confirmed
+//! in time is replaced at the same nonce with bumped fees, instead of leaving the next transactions
+//! of the chain stuck behind it.
+
+use alloy::primitives::B256;
+use alloy::transports::TransportError;
+
+/// The JSON-RPC error code that geth compatible nodes return when they reject a transaction.
+const TX_REJECTED_ERROR_CODE: i64 = -32000;
+
+/// The EIP-1559 fees of a transaction, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl Fees {
+    /// The fees of a transaction replacing one sent with these fees: the `current` fees, raised to
+    /// these fees bumped by `bump_percent` where they are lower. Nodes only accept a replacement
+    /// that bumps both fees, by at least 10% for geth.
+    pub fn bumped(&self, current: Fees, bump_percent: u64) -> Fees {
+        let bump = |fee: u128| fee + (fee * bump_percent as u128).div_ceil(100);
+        let max_priority_fee_per_gas = current
+            .max_priority_fee_per_gas
+            .max(bump(self.max_priority_fee_per_gas));
+
+        Fees {
+            max_fee_per_gas: current
+                .max_fee_per_gas
+                .max(bump(self.max_fee_per_gas))
+                .max(max_priority_fee_per_gas),
+            max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// A transaction sent with the local signer that has not been confirmed yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTx {
+    pub nonce: u64,
+    /// The hashes of the transactions sent at this nonce, the latest last. Only one of them can be
+    /// mined.
+    pub tx_hashes: Vec<B256>,
+    /// The fees of the latest transaction.
+    pub fees: Fees,
+}
+
+impl PendingTx {
+    /// Record a transaction sent at `nonce`. Replaces the pending transaction if it was at another
+    /// nonce, whose transactions can then no longer be mined.
+    pub fn record(pending: &mut Option<PendingTx>, nonce: u64, tx_hash: B256, fees: Fees) {
+        match pending {
+            Some(tx) if tx.nonce == nonce => {
+                tx.tx_hashes.push(tx_hash);
+                tx.fees = fees;
+            }
+            _ => {
+                *pending = Some(PendingTx {
+                    nonce,
+                    tx_hashes: vec![tx_hash],
+                    fees,
+                })
+            }
+        }
+    }
+}
+
+/// Whether the node rejected a transaction because its fees are too low to replace the transaction
+/// at its nonce, or to enter the mempool. The rejection code is shared by every rejection reason, so
+/// the reason is read from the error message of the node's response.
+pub fn is_underpriced(error: &TransportError) -> bool {
+    error.as_error_resp().is_some_and(|payload| {
+        payload.code == TX_REJECTED_ERROR_CODE && payload.message.contains("underpriced")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    use super::*;
+
+    #[test]
+    fn test_bumped_fees() {
+        let sent = Fees {
+            max_fee_per_gas: 100,
+            max_priority_fee_per_gas: 10,
+        };
+
+        // The fees have not moved, so both are bumped.
+        let bumped = sent.bumped(sent, 15);
+        assert_eq!(
+            bumped,
+            Fees {
+                max_fee_per_gas: 115,
+                max_priority_fee_per_gas: 12,
+            }
+        );
+
+        // The base fee rose above the bump, so the current max fee is used.
+        let current = Fees {
+            max_fee_per_gas: 200,
+            max_priority_fee_per_gas: 5,
+        };
+        assert_eq!(
+            sent.bumped(current, 15),
+            Fees {
+                max_fee_per_gas: 200,
+                max_priority_fee_per_gas: 12,
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_pending_tx() {
+        let fees = Fees {
+            max_fee_per_gas: 100,
+            max_priority_fee_per_gas: 10,
+        };
+        let mut pending = None;
+
+        PendingTx::record(&mut pending, 7, B256::with_last_byte(1), fees);
+        PendingTx::record(
+            &mut pending,
+            7,
+            B256::with_last_byte(2),
+            fees.bumped(fees, 10),
+        );
+        let tx = pending.clone().unwrap();
+        assert_eq!(tx.nonce, 7);
+        assert_eq!(
+            tx.tx_hashes,
+            vec![B256::with_last_byte(1), B256::with_last_byte(2)]
+        );
+        assert_eq!(tx.fees.max_fee_per_gas, 110);
+
+        PendingTx::record(&mut pending, 8, B256::with_last_byte(3), fees);
+        assert_eq!(pending.unwrap().tx_hashes, vec![B256::with_last_byte(3)]);
+    }
+
+    #[test]
+    fn test_is_underpriced() {
+        let rejected = |code, message: &'static str| {
+            TransportError::ErrorResp(ErrorPayload {
+                code,
+                message: message.into(),
+                data: None,
+            })
+        };
+
+        assert!(is_underpriced(&rejected(
+            -32000,
+            "replacement transaction underpriced"
+        )));
+        assert!(!is_underpriced(&rejected(-32000, "nonce too low")));
+        assert!(!is_underpriced(&rejected(-32601, "underpriced")));
+    }
+}
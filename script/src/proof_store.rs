@@ -0,0 +1,246 @@
+//! An on-disk store of generated SP1 Vector proofs, so a proof is only paid for once. Proofs are
+//! keyed by the proof type and its inputs, and stored under the Avail chain, the verifying key and
+//! the proof system they were generated for.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use sp1_sdk::SP1ProofWithPublicValues;
+
+use crate::config::ProofSystem;
+
+/// The inputs that uniquely determine an SP1 Vector proof for a given verifying key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProofKey {
+    HeaderRange {
+        trusted_block: u32,
+        target_block: u32,
+        tree_size: u32,
+    },
+    Rotate {
+        /// The authority set id that signed the epoch end block of the rotation.
+        authority_set_id: u64,
+    },
+}
+
+impl ProofKey {
+    fn file_name(&self) -> String {
+        match self {
+            ProofKey::HeaderRange {
+                trusted_block,
+                target_block,
+                tree_size,
+            } => format!(
+                "header-range-{}-{}-{}.bin",
+                trusted_block, target_block, tree_size
+            ),
+            ProofKey::Rotate { authority_set_id } => format!("rotate-{}.bin", authority_set_id),
+        }
+    }
+}
+
+/// A directory of proofs, laid out as `{avail_chain_id}/{vkey}/{proof_system}/{proof}.bin`.
+#[derive(Debug, Clone)]
+pub struct ProofStore {
+    dir: PathBuf,
+}
+
+impl ProofStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(
+        &self,
+        avail_chain_id: &str,
+        vkey: &str,
+        proof_system: ProofSystem,
+        key: &ProofKey,
+    ) -> PathBuf {
+        let proof_system = match proof_system {
+            ProofSystem::Plonk => "plonk",
+            ProofSystem::Groth16 => "groth16",
+        };
+
+        self.dir
+            .join(avail_chain_id)
+            .join(vkey)
+            .join(proof_system)
+            .join(key.file_name())
+    }
+
+    /// Get a stored proof, or None if it has not been generated yet.
+    pub fn get(
+        &self,
+        avail_chain_id: &str,
+        vkey: &str,
+        proof_system: ProofSystem,
+        key: &ProofKey,
+    ) -> Result<Option<SP1ProofWithPublicValues>> {
+        let path = self.path(avail_chain_id, vkey, proof_system, key);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&path).context(format!("Failed to read {}", path.display()))?;
+        let proof = bincode::deserialize(&bytes)
+            .context(format!("Failed to decode proof {}", path.display()))?;
+
+        Ok(Some(proof))
+    }
+
+    /// Store a proof. The proof is written to a temporary file first, so a crash never leaves a
+    /// partially written proof behind.
+    pub fn put(
+        &self,
+        avail_chain_id: &str,
+        vkey: &str,
+        proof_system: ProofSystem,
+        key: &ProofKey,
+        proof: &SP1ProofWithPublicValues,
+    ) -> Result<()> {
+        let path = self.path(avail_chain_id, vkey, proof_system, key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bincode::serialize(proof)?)
+            .context(format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path).context(format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_sdk::{SP1Proof, SP1PublicValues};
+
+    use super::*;
+
+    const CHAIN: &str = "test-chain";
+    const VKEY: &str = "0x01";
+
+    /// A fresh proof store in the system temp dir.
+    fn test_store(name: &str) -> ProofStore {
+        let dir = std::env::temp_dir().join(format!(
+            "sp1-vector-proof-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        ProofStore::new(dir)
+    }
+
+    fn test_proof(public_values: &[u8]) -> SP1ProofWithPublicValues {
+        SP1ProofWithPublicValues {
+            proof: SP1Proof::Core(vec![]),
+            public_values: SP1PublicValues::from(public_values),
+            sp1_version: "v5.0.0".to_string(),
+            tee_proof: None,
+        }
+    }
+
+    #[test]
+    fn test_put_get() {
+        let store = test_store("put-get");
+        let key = ProofKey::HeaderRange {
+            trusted_block: 1,
+            target_block: 2,
+            tree_size: 4,
+        };
+
+        assert!(store
+            .get(CHAIN, VKEY, ProofSystem::Plonk, &key)
+            .unwrap()
+            .is_none());
+
+        store
+            .put(
+                CHAIN,
+                VKEY,
+                ProofSystem::Plonk,
+                &key,
+                &test_proof(&[1, 2, 3]),
+            )
+            .unwrap();
+        let proof = store
+            .get(CHAIN, VKEY, ProofSystem::Plonk, &key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof.public_values.as_slice(), &[1, 2, 3]);
+        assert_eq!(proof.sp1_version, "v5.0.0");
+
+        // Proofs for another proof system are stored separately.
+        assert!(store
+            .get(CHAIN, VKEY, ProofSystem::Groth16, &key)
+            .unwrap()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_keys_do_not_collide() {
+        let store = test_store("keys");
+        let header_range = ProofKey::HeaderRange {
+            trusted_block: 1,
+            target_block: 1,
+            tree_size: 1,
+        };
+        let rotate = ProofKey::Rotate {
+            authority_set_id: 1,
+        };
+        assert_ne!(header_range.file_name(), rotate.file_name());
+
+        store
+            .put(
+                CHAIN,
+                VKEY,
+                ProofSystem::Plonk,
+                &header_range,
+                &test_proof(&[1]),
+            )
+            .unwrap();
+        store
+            .put(CHAIN, VKEY, ProofSystem::Plonk, &rotate, &test_proof(&[2]))
+            .unwrap();
+        let get = |key| {
+            store
+                .get(CHAIN, VKEY, ProofSystem::Plonk, key)
+                .unwrap()
+                .unwrap()
+                .public_values
+                .as_slice()
+                .to_vec()
+        };
+        assert_eq!(get(&header_range), vec![1]);
+        assert_eq!(get(&rotate), vec![2]);
+
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_leftover_tmp_file_is_ignored() {
+        let store = test_store("tmp");
+        let key = ProofKey::Rotate {
+            authority_set_id: 7,
+        };
+
+        // A crash between writing the temporary file and renaming it leaves only the `.tmp` file.
+        let tmp_path = store
+            .path(CHAIN, VKEY, ProofSystem::Plonk, &key)
+            .with_extension("tmp");
+        fs::create_dir_all(tmp_path.parent().unwrap()).unwrap();
+        fs::write(&tmp_path, b"partial").unwrap();
+
+        assert!(store
+            .get(CHAIN, VKEY, ProofSystem::Plonk, &key)
+            .unwrap()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+}
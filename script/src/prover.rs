@@ -0,0 +1,191 @@
+//! The prover backends the operator generates proofs with, selected by [`ProverConfig`].
+//!
+//! The HTTP backend delegates proving to a custom prover with a simple protocol: the operator POSTs
+//! a bincode encoded [`HttpProveRequest`] to the configured URL, and the prover responds with the
+//! bincode encoded `SP1ProofWithPublicValues`, or an error status with a message. The prover must
+//! be set up with the same program, which it can check against the request's verifying key hash.
+//! Proofs from the HTTP prover are verified before they are used.
+
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::network::FulfillmentStrategy;
+use sp1_sdk::{
+    CpuProver, HashableKey, NetworkProver, Prover, ProverClient, SP1Proof,
+    SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
+};
+
+use crate::config::{NetworkStrategy, ProofSystem, ProverConfig};
+
+/// The request body of the HTTP prover.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpProveRequest {
+    /// The hash of the verifying key of the program to prove, as in the contract.
+    pub vkey_hash: String,
+    pub proof_system: ProofSystem,
+    pub stdin: SP1Stdin,
+}
+
+enum ProverBackend {
+    Network {
+        prover: NetworkProver,
+        strategy: FulfillmentStrategy,
+    },
+    Cpu(Arc<CpuProver>),
+    Mock(Arc<CpuProver>),
+    Http {
+        client: reqwest::Client,
+        url: String,
+        /// Verifies the proofs of the HTTP prover.
+        verifier: CpuProver,
+    },
+}
+
+/// A prover backend set up with the program to prove.
+pub struct OperatorProver {
+    backend: ProverBackend,
+    pk: Arc<SP1ProvingKey>,
+    vk: SP1VerifyingKey,
+}
+
+impl OperatorProver {
+    /// Build the backend selected by the config, and set it up with the program.
+    pub fn new(config: &ProverConfig, elf: &[u8]) -> Self {
+        let backend = match config {
+            ProverConfig::Network { strategy } => ProverBackend::Network {
+                prover: ProverClient::builder().network().build(),
+                strategy: match strategy {
+                    NetworkStrategy::Hosted => FulfillmentStrategy::Hosted,
+                    NetworkStrategy::Reserved => FulfillmentStrategy::Reserved,
+                    NetworkStrategy::Auction => FulfillmentStrategy::Auction,
+                },
+            },
+            ProverConfig::Cpu => {
+                ProverBackend::Cpu(Arc::new(ProverClient::builder().cpu().build()))
+            }
+            ProverConfig::Mock => {
+                ProverBackend::Mock(Arc::new(ProverClient::builder().mock().build()))
+            }
+            ProverConfig::Http { url } => ProverBackend::Http {
+                client: reqwest::Client::new(),
+                url: url.clone(),
+                verifier: ProverClient::builder().cpu().build(),
+            },
+        };
+
+        let (pk, vk) = match &backend {
+            ProverBackend::Network { prover, .. } => prover.setup(elf),
+            ProverBackend::Cpu(prover) | ProverBackend::Mock(prover) => prover.setup(elf),
+            ProverBackend::Http { verifier, .. } => verifier.setup(elf),
+        };
+
+        Self {
+            backend,
+            pk: Arc::new(pk),
+            vk,
+        }
+    }
+
+    pub fn vk(&self) -> &SP1VerifyingKey {
+        &self.vk
+    }
+
+    /// Whether the proofs only pass mock verifiers.
+    pub fn is_mock(&self) -> bool {
+        matches!(self.backend, ProverBackend::Mock(_))
+    }
+
+    /// Generate a proof of the program for the given stdin, or an error after the timeout.
+    ///
+    /// A local proof that times out is abandoned, but keeps running on its blocking thread until it
+    /// completes, as the CPU prover cannot be cancelled.
+    pub async fn prove(
+        &self,
+        stdin: &SP1Stdin,
+        proof_system: ProofSystem,
+        timeout: Duration,
+    ) -> Result<SP1ProofWithPublicValues> {
+        match &self.backend {
+            ProverBackend::Network { prover, strategy } => {
+                let request = prover
+                    .prove(&self.pk, stdin)
+                    .strategy(*strategy)
+                    .skip_simulation(true)
+                    .timeout(timeout);
+                match proof_system {
+                    ProofSystem::Plonk => request.plonk().run_async().await,
+                    ProofSystem::Groth16 => request.groth16().run_async().await,
+                }
+            }
+            ProverBackend::Cpu(prover) | ProverBackend::Mock(prover) => {
+                // Proving on the CPU blocks for a long time, so keep it off the async workers.
+                let prover = prover.clone();
+                let pk = self.pk.clone();
+                let stdin = stdin.clone();
+                let task = tokio::task::spawn_blocking(move || {
+                    let request = prover.prove(&pk, &stdin);
+                    match proof_system {
+                        ProofSystem::Plonk => request.plonk().run(),
+                        ProofSystem::Groth16 => request.groth16().run(),
+                    }
+                });
+
+                tokio::time::timeout(timeout, task)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("Local proof timed out after {:?}", timeout))?
+                    .context("Local proving task failed")?
+            }
+            ProverBackend::Http {
+                client,
+                url,
+                verifier,
+            } => {
+                let request = HttpProveRequest {
+                    vkey_hash: self.vk.bytes32(),
+                    proof_system,
+                    stdin: stdin.clone(),
+                };
+                let mut builder = client
+                    .post(url)
+                    .header("content-type", "application/octet-stream")
+                    .body(bincode::serialize(&request)?)
+                    .timeout(timeout);
+                if let Ok(api_key) = env::var("PROVER_HTTP_API_KEY") {
+                    builder = builder.bearer_auth(api_key);
+                }
+
+                let response = builder.send().await?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(anyhow::anyhow!(
+                        "HTTP prover returned {}: {}",
+                        status,
+                        response.text().await.unwrap_or_default()
+                    ));
+                }
+
+                let proof: SP1ProofWithPublicValues =
+                    bincode::deserialize(&response.bytes().await?)
+                        .context("Failed to decode the proof of the HTTP prover")?;
+                let matches_proof_system = match proof_system {
+                    ProofSystem::Plonk => matches!(proof.proof, SP1Proof::Plonk(_)),
+                    ProofSystem::Groth16 => matches!(proof.proof, SP1Proof::Groth16(_)),
+                };
+                if !matches_proof_system {
+                    return Err(anyhow::anyhow!(
+                        "HTTP prover returned a proof of the wrong proof system, expected {:?}",
+                        proof_system
+                    ));
+                }
+                verifier
+                    .verify(&proof, &self.vk)
+                    .context("Invalid proof from the HTTP prover")?;
+
+                Ok(proof)
+            }
+        }
+    }
+}
@@ -13,6 +13,7 @@ use tracing::info;
 
 /// Get the gas limit associated with the chain id. Note: These values have been found through
 /// trial and error and can be configured.
+#[deprecated(note = "use `fees::FeePolicy::gas_limit`, which the operator configures per chain")]
 pub fn get_gas_limit(chain_id: u64) -> u64 {
     if chain_id == 42161 || chain_id == 421614 {
         25_000_000
@@ -23,6 +24,7 @@ pub fn get_gas_limit(chain_id: u64) -> u64 {
 
 /// Get the gas fee cap associated with the chain id, using the provider to get the gas price. Note:
 /// These values have been found through trial and error and can be configured.
+#[deprecated(note = "use `fees::FeePolicy::estimate`, which the operator configures per chain")]
 pub async fn get_fee_cap<P, N>(chain_id: u64, provider: &P) -> u128
 where
     P: Provider<N>,
@@ -43,7 +45,7 @@ where
     (gas_price * (100 + multiplier)) / 100
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KMSRelayStatus {
     Unknown = 0,
     Relayed = 1,
@@ -53,13 +55,60 @@ pub enum KMSRelayStatus {
     InvalidAuthenticationToken = 5,
 }
 
-/// Relay request arguments for KMS relayer.
+impl KMSRelayStatus {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            1 => Self::Relayed,
+            2 => Self::PreflightError,
+            3 => Self::SimulationFailure,
+            4 => Self::RelayFailure,
+            5 => Self::InvalidAuthenticationToken,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Relayed => "relayed",
+            Self::PreflightError => "preflight_error",
+            Self::SimulationFailure => "simulation_failure",
+            Self::RelayFailure => "relay_failure",
+            Self::InvalidAuthenticationToken => "invalid_authentication_token",
+        }
+    }
+}
+
+/// The error returned by [`relay_with_kms`] when the relayer rejected every attempt, with the status
+/// of the last attempt.
+#[derive(Debug)]
+pub struct KMSRelayError {
+    pub status: KMSRelayStatus,
+    pub message: String,
+}
+
+impl std::fmt::Display for KMSRelayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to relay transaction: {}", self.message)
+    }
+}
+
+impl std::error::Error for KMSRelayError {}
+
+/// Relay request arguments for KMS relayer. The gas fields are left to the relayer if unset, and are
+/// only sent for chains with `kms_gas_fields` enabled, as older relayers reject unknown fields.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct KMSRelayRequest {
     pub chain_id: u64,
     pub address: String,
     pub calldata: String,
     pub platform_request: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<u128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<u128>,
 }
 
 /// Response from KMS relayer.
@@ -89,10 +138,11 @@ pub async fn relay_with_kms(args: &KMSRelayRequest, num_retries: u32) -> Result<
                     .expect("KMS request always returns a message");
                 tracing::warn!("KMS relay attempt {} failed: {}", attempt, error_message);
                 if attempt == num_retries {
-                    return Err(anyhow::anyhow!(
-                        "Failed to relay transaction: {}",
-                        error_message
-                    ));
+                    return Err(KMSRelayError {
+                        status: KMSRelayStatus::from_code(response.status),
+                        message: error_message,
+                    }
+                    .into());
                 }
             }
         }
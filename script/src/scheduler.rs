@@ -0,0 +1,160 @@
+//! Alignment of the header range requests of several chains, so that the chains can share proofs.
+//!
+//! A header range proof commits to its trusted and target blocks, so it can only be relayed to the
+//! chains whose contract is at the trusted block. The scheduler aligns the targets of the chains at
+//! the same trusted block, and steps lagging chains to the target of a chain ahead of them, so that
+//! they share proofs from the next loop on.
+
+use std::collections::BTreeMap;
+
+use services::input::HeaderRangeRequestData;
+
+/// The header range request of a chain, as found from its contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainRequest {
+    pub chain_id: u64,
+    pub request: HeaderRangeRequestData,
+    /// The commitment tree size of the chain's contract.
+    pub tree_size: u32,
+}
+
+/// Align the targets of the header range requests, returning the aligned requests in the same order.
+///
+/// The chains are grouped by trusted block and tree size. Every target in a group is valid for all
+/// of the chains in the group, as they share the trusted block, the tree size and the authority set.
+/// The groups are aligned from the furthest ahead:
+/// - If the aligned target of a group further ahead, with the same tree size, is at or below every
+///   target of the group, the group steps to the highest such target. Both groups are then at the
+///   same block, so they share proofs from the next loop on.
+/// - Otherwise, the group steps to the highest target of the group.
+///
+/// Every aligned target is the target of one of the requests, so it has a justification.
+pub fn align_header_range_requests(requests: &[ChainRequest]) -> Vec<ChainRequest> {
+    let mut groups: BTreeMap<(u32, u32), Vec<&ChainRequest>> = BTreeMap::new();
+    for request in requests {
+        groups
+            .entry((request.request.trusted_block, request.tree_size))
+            .or_default()
+            .push(request);
+    }
+
+    let mut targets: BTreeMap<(u32, u32), HeaderRangeRequestData> = BTreeMap::new();
+    for (&(trusted_block, tree_size), members) in groups.iter().rev() {
+        let min_target = members
+            .iter()
+            .map(|m| m.request.target_block)
+            .min()
+            .expect("Groups are never empty");
+
+        // The highest aligned target of a group ahead with the same tree size that every chain in
+        // this group can step to. The groups ahead are already aligned, as they are visited first.
+        let ahead = targets
+            .iter()
+            .filter(|(&(other_trusted_block, other_tree_size), other)| {
+                other_tree_size == tree_size
+                    && other_trusted_block > trusted_block
+                    && other.target_block <= min_target
+            })
+            .map(|(_, other)| *other)
+            .max_by_key(|other| other.target_block);
+
+        let request = match ahead {
+            Some(ahead) => HeaderRangeRequestData {
+                trusted_block,
+                target_block: ahead.target_block,
+                is_target_epoch_end_block: ahead.is_target_epoch_end_block,
+            },
+            None => {
+                let target_block = members
+                    .iter()
+                    .map(|m| m.request.target_block)
+                    .max()
+                    .expect("Groups are never empty");
+                HeaderRangeRequestData {
+                    trusted_block,
+                    target_block,
+                    is_target_epoch_end_block: members.iter().any(|m| {
+                        m.request.target_block == target_block
+                            && m.request.is_target_epoch_end_block
+                    }),
+                }
+            }
+        };
+        targets.insert((trusted_block, tree_size), request);
+    }
+
+    requests
+        .iter()
+        .map(|request| ChainRequest {
+            request: targets[&(request.request.trusted_block, request.tree_size)],
+            ..*request
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain_request(chain_id: u64, trusted_block: u32, target_block: u32) -> ChainRequest {
+        ChainRequest {
+            chain_id,
+            request: HeaderRangeRequestData {
+                trusted_block,
+                target_block,
+                is_target_epoch_end_block: false,
+            },
+            tree_size: 1024,
+        }
+    }
+
+    #[test]
+    fn test_align_header_range_requests() {
+        let requests = vec![
+            // Two chains at the same trusted block with different block update intervals.
+            chain_request(1, 1000, 1720),
+            chain_request(2, 1000, 1800),
+            // A lagging chain that cannot reach the aligned target of chains 1 and 2.
+            chain_request(3, 900, 1440),
+            // A lagging chain that can reach the aligned target of chains 1 and 2.
+            chain_request(6, 800, 1850),
+            // A chain that is too far behind to reach any other chain.
+            chain_request(4, 100, 720),
+            // A chain with a different tree size is never aligned with the others.
+            ChainRequest {
+                tree_size: 512,
+                ..chain_request(5, 950, 1080)
+            },
+        ];
+
+        let targets: Vec<(u64, u32, u32)> = align_header_range_requests(&requests)
+            .iter()
+            .map(|r| (r.chain_id, r.request.trusted_block, r.request.target_block))
+            .collect();
+
+        assert_eq!(
+            targets,
+            vec![
+                (1, 1000, 1800),
+                (2, 1000, 1800),
+                (3, 900, 1440),
+                (6, 800, 1800),
+                (4, 100, 720),
+                (5, 950, 1080),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_keeps_epoch_end_target() {
+        let mut epoch_end = chain_request(2, 1000, 1500);
+        epoch_end.request.is_target_epoch_end_block = true;
+        let requests = vec![chain_request(1, 1000, 1440), epoch_end];
+
+        let aligned = align_header_range_requests(&requests);
+        for request in aligned {
+            assert_eq!(request.request.target_block, 1500);
+            assert!(request.request.is_target_epoch_end_block);
+        }
+    }
+}
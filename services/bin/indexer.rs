@@ -1,17 +1,145 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use avail_subxt::primitives::Header;
 use avail_subxt::RpcParams;
+use clap::{Parser, Subcommand};
 use codec::Decode;
 use serde::de::Error;
 use serde::Deserialize;
+use services::authority_set::record_authority_set_change;
+use services::backfill::backfill_justifications;
 use services::input::RpcDataFetcher;
-use services::postgres::PostgresClient;
+use services::retention::{Pruner, RetentionPolicy};
+use services::store::{self, JustificationStore};
 use services::types::{Commit, GrandpaJustification};
 use sp_core::bytes;
 use subxt::backend::rpc::RpcSubscription;
-use tracing::{debug, error, info};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 
 use services::Timeout;
 
+/// The number of concurrent finality proof requests when catching up after a restart.
+const CATCH_UP_CONCURRENCY: usize = 4;
+
+/// The number of legacy justifications converted to the SCALE encoding per batch.
+const LEGACY_CONVERSION_BATCH_SIZE: usize = 1000;
+
+/// Avail's block time is 20 seconds, as long as this is greater than that, we should be fine.
+const SUBSCRIPTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The delay before the first retry of a failed subscription. Doubled after every consecutive
+/// failure, up to `MAX_RETRY_DELAY`.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// How often the health of each network is reported, and stopped network tasks are restarted.
+const HEALTH_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// An Avail network to index.
+#[derive(Debug, Clone, Deserialize)]
+struct NetworkConfig {
+    avail_chain_id: String,
+    avail_url: String,
+}
+
+impl NetworkConfig {
+    /// Tries to read from the `NETWORKS_PATH` environment variable, then the default path
+    /// (`networks.json`).
+    ///
+    /// If neither are set, it will try to use [`Self::from_env`].
+    fn fetch() -> anyhow::Result<Vec<Self>> {
+        const DEFAULT_PATH: &str = "networks.json";
+
+        let path = std::env::var("NETWORKS_PATH").unwrap_or(DEFAULT_PATH.to_string());
+
+        Self::from_file(&path).or_else(|_| {
+            info!("No networks file found, trying env.");
+            Self::from_env().map(|c| vec![c])
+        })
+    }
+
+    /// Tries to read from the `AVAIL_CHAIN_ID` and `AVAIL_URL` environment variables.
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            avail_chain_id: std::env::var("AVAIL_CHAIN_ID")?,
+            avail_url: std::env::var("AVAIL_URL")?,
+        })
+    }
+
+    fn from_file(path: &str) -> anyhow::Result<Vec<Self>> {
+        debug!("Reading networks from file: {}", path);
+
+        let file = std::fs::read_to_string(path)?;
+
+        Ok(serde_json::from_str(&file)?)
+    }
+}
+
+/// The health of the indexing task of a network.
+#[derive(Debug, Clone, Default)]
+struct NetworkHealth {
+    /// Whether the subscription is currently active.
+    subscribed: bool,
+    /// The number of consecutive subscription attempts that failed without a justification.
+    consecutive_failures: u32,
+    /// The block of the latest justification received from the subscription.
+    latest_block: Option<u32>,
+    /// When the latest justification was received from the subscription.
+    latest_justification_at: Option<Instant>,
+    /// The number of justification rows removed by the pruner.
+    rows_pruned: Arc<AtomicU64>,
+}
+
+/// The health of every network, keyed by Avail chain id.
+type HealthRegistry = Arc<Mutex<BTreeMap<String, NetworkHealth>>>;
+
+fn update_health(
+    health: &HealthRegistry,
+    avail_chain_id: &str,
+    f: impl FnOnce(&mut NetworkHealth),
+) {
+    f(health
+        .lock()
+        .unwrap()
+        .entry(avail_chain_id.to_string())
+        .or_default());
+}
+
+/// Log the health of every network. Networks without a justification within the subscription
+/// timeout are reported as unhealthy.
+fn report_health(health: &HealthRegistry) {
+    for (avail_chain_id, network_health) in health.lock().unwrap().iter() {
+        let since_latest_justification = network_health
+            .latest_justification_at
+            .map(|latest_justification_at| latest_justification_at.elapsed());
+        let healthy = network_health.subscribed
+            && since_latest_justification.is_some_and(|elapsed| elapsed <= SUBSCRIPTION_TIMEOUT);
+
+        if healthy {
+            info!(
+                "Network {} is healthy: latest justification for block {:?} received {:?} ago, {} rows pruned",
+                avail_chain_id,
+                network_health.latest_block,
+                since_latest_justification.unwrap_or_default(),
+                network_health.rows_pruned.load(Ordering::Relaxed)
+            );
+        } else {
+            warn!(
+                "Network {} is unhealthy: subscribed: {}, {} consecutive failures, latest justification for block {:?} received {:?} ago",
+                avail_chain_id,
+                network_health.subscribed,
+                network_health.consecutive_failures,
+                network_health.latest_block,
+                since_latest_justification
+            );
+        }
+    }
+}
+
 /// The justification type that the Avail Subxt client returns for justifications. Needs a custom
 /// deserializer, so we can't use the equivalent `GrandpaJustification` type.
 #[derive(Clone, Debug, Decode)]
@@ -43,39 +171,97 @@ impl<'de> Deserialize<'de> for AvailSubscriptionGrandpaJustification {
     }
 }
 
-/// When the subscription yields events, add them to the indexer DB. If the subscription fails,
-/// exit so the outer loop can re-initialize it.
+/// Verify a justification against the authority set that attested to its target block, and add it to
+/// the indexer DB if it is valid. Invalid justifications are recorded with the reason they were
+/// rejected, and never replace a stored justification.
+async fn store_verified_justification(
+    store: &dyn JustificationStore,
+    fetcher: &RpcDataFetcher,
+    justification: GrandpaJustification,
+) {
+    let block_number = justification.commit.target_number;
+
+    let header = match fetcher.verify_grandpa_justification(&justification).await {
+        Ok(header) => header,
+        Err(e) => {
+            warn!("Rejected justification for block {}: {:?}", block_number, e);
+            if let Err(e) = store
+                .add_rejected_justification(&fetcher.avail_chain_id, &justification, &e.to_string())
+                .await
+            {
+                error!(
+                    "Error recording rejected justification in the store: {:?}",
+                    e
+                );
+            }
+            return;
+        }
+    };
+
+    if let Err(e) = store
+        .add_justification(&fetcher.avail_chain_id, justification)
+        .await
+    {
+        error!("Error adding justification to the store: {:?}", e);
+    }
+
+    // Every epoch end block has a justification, so all authority set changes are seen here.
+    if let Err(e) = record_authority_set_change(fetcher, store, &header).await {
+        error!(
+            "Error recording authority set change at block {}: {:?}",
+            block_number, e
+        );
+    }
+}
+
+/// When the subscription yields events, verify them and add them to the indexer DB. If the
+/// subscription fails, exit so the outer loop can re-initialize it. Returns whether any
+/// justification was received.
 async fn handle_subscription(
     sub: &mut RpcSubscription<AvailSubscriptionGrandpaJustification>,
-    postgres_client: &PostgresClient,
+    store: &dyn JustificationStore,
     fetcher: &RpcDataFetcher,
-    timeout_duration: std::time::Duration,
-) {
+    health: &HealthRegistry,
+) -> bool {
+    let avail_chain_id = &fetcher.avail_chain_id;
+    let mut received = false;
+
     loop {
-        match sub.next().timeout(timeout_duration).await {
+        match sub.next().timeout(SUBSCRIPTION_TIMEOUT).await {
             Ok(Some(Ok(justification))) => {
+                let block_number = justification.commit.target_number;
                 debug!(
-                    "New justification from block {}",
-                    justification.commit.target_number
+                    "New justification for chain {} from block {}",
+                    avail_chain_id, block_number
                 );
-                if let Err(e) = postgres_client
-                    .add_justification(&fetcher.avail_chain_id, justification.into())
-                    .await
-                {
-                    error!("Error adding justification to PostgreSQL: {:?}", e);
-                }
+                received = true;
+                update_health(health, avail_chain_id, |network_health| {
+                    network_health.consecutive_failures = 0;
+                    network_health.latest_block = Some(block_number);
+                    network_health.latest_justification_at = Some(Instant::now());
+                });
+                store_verified_justification(store, fetcher, justification.into()).await;
             }
             Ok(None) => {
-                error!("Subscription ended unexpectedly");
-                return;
+                error!(
+                    "Subscription for chain {} ended unexpectedly",
+                    avail_chain_id
+                );
+                return received;
             }
             Ok(Some(Err(e))) => {
-                error!("Error in subscription: {:?}", e);
-                return;
+                error!(
+                    "Error in subscription for chain {}: {:?}",
+                    avail_chain_id, e
+                );
+                return received;
             }
             Err(_) => {
-                error!("Timeout reached. No event received in the last minute.");
-                return;
+                error!(
+                    "Timeout reached for chain {}. No event received in the last minute.",
+                    avail_chain_id
+                );
+                return received;
             }
         }
     }
@@ -96,50 +282,350 @@ async fn initialize_subscription(
         .await
 }
 
-/// Listen for justifications. If the subscription fails to yield a justification within the timeout
-/// or errors, it will re-initialize the subscription.
-async fn listen_for_justifications() {
-    // Avail's block time is 20 seconds, as long as this is greater than that, we should be fine.
-    let timeout_duration = std::time::Duration::from_secs(60);
-    // Time to wait before retrying the subscription.
-    let retry_delay = std::time::Duration::from_secs(5);
+/// Compare the latest justification in the DB with the finalized head, and fill the gap between them
+/// in the background while live indexing continues. Returns None if there is no gap to fill.
+async fn spawn_catch_up(
+    fetcher: Arc<RpcDataFetcher>,
+    store: Arc<dyn JustificationStore>,
+) -> Option<JoinHandle<()>> {
+    let latest_block = match store.get_latest_block_number(&fetcher.avail_chain_id).await {
+        Ok(Some(latest_block)) => latest_block,
+        Ok(None) => {
+            info!(
+                "No justifications stored for chain {}, skipping catch-up",
+                fetcher.avail_chain_id
+            );
+            return None;
+        }
+        Err(e) => {
+            error!("Failed to get the latest stored block: {:?}", e);
+            return None;
+        }
+    };
+
+    let head_block = fetcher.get_head().await.number;
+    if head_block <= latest_block {
+        debug!(
+            "Indexer is up to date with the finalized head {}",
+            head_block
+        );
+        return None;
+    }
+
+    info!(
+        "Indexer is {} blocks behind the finalized head (latest stored block {}, finalized head {}), catching up in the background",
+        head_block - latest_block,
+        latest_block,
+        head_block
+    );
+
+    Some(tokio::spawn(async move {
+        match backfill_justifications(
+            &fetcher,
+            store.as_ref(),
+            latest_block + 1,
+            head_block,
+            CATCH_UP_CONCURRENCY,
+        )
+        .await
+        {
+            Ok(stats) => info!(
+                "Caught up {} blocks from block {} to block {} in {:?}: {} justifications added, {} rejected",
+                head_block - latest_block,
+                latest_block + 1,
+                head_block,
+                stats.duration,
+                stats.added,
+                stats.rejected
+            ),
+            Err(e) => error!(
+                "Catch-up from block {} to block {} failed: {:?}",
+                latest_block + 1,
+                head_block,
+                e
+            ),
+        }
+    }))
+}
+
+/// Convert the legacy justifications in the store, which are only stored as JSON, to the SCALE
+/// encoding in the background.
+fn spawn_legacy_conversion(store: Arc<dyn JustificationStore>) {
+    tokio::spawn(async move {
+        let start_time = Instant::now();
+        match store::convert_legacy_justifications(store.as_ref(), LEGACY_CONVERSION_BATCH_SIZE)
+            .await
+        {
+            Ok(converted) => info!(
+                "Converted {} legacy justifications to the SCALE encoding in {:?}",
+                converted,
+                start_time.elapsed()
+            ),
+            Err(e) => error!("Failed to convert legacy justifications: {:?}", e),
+        }
+    });
+}
+
+/// The background tasks of one network, aborted when dropped.
+#[derive(Default)]
+struct NetworkTasks {
+    /// The in-progress catch-up task, if any. Only one catch-up runs at a time.
+    catch_up: Option<JoinHandle<()>>,
+    /// The pruning task, if a retention policy is configured. Restarted with the new fetcher if it
+    /// stopped.
+    pruner: Option<JoinHandle<()>>,
+}
+
+impl Drop for NetworkTasks {
+    fn drop(&mut self) {
+        for handle in [self.catch_up.take(), self.pruner.take()]
+            .into_iter()
+            .flatten()
+        {
+            handle.abort();
+        }
+    }
+}
+
+/// Index the justifications of one network. If the subscription fails to yield a justification
+/// within the timeout or errors, it will re-initialize the subscription, with an exponential backoff
+/// while it keeps failing.
+///
+/// On startup and after every re-initialization, any gap between the DB and the finalized head is
+/// filled in the background. The background tasks are aborted when this task stops.
+async fn index_network(
+    network: NetworkConfig,
+    store: Arc<dyn JustificationStore>,
+    retention_policy: Option<RetentionPolicy>,
+    health: HealthRegistry,
+) {
+    let avail_chain_id = network.avail_chain_id.clone();
+    let mut retry_delay = INITIAL_RETRY_DELAY;
+
+    // Aborted when this task stops, so a restarted network doesn't leave duplicates running.
+    let mut tasks = NetworkTasks::default();
 
     loop {
-        info!("Initializing fetcher and subscription...");
+        info!(
+            "Initializing fetcher and subscription for chain {}...",
+            avail_chain_id
+        );
 
-        let Ok(fetcher) = RpcDataFetcher::new().timeout(timeout_duration).await else {
-            error!("Failed to initialize fetcher after timeout");
-            continue;
-        };
+        let mut received = false;
+        match RpcDataFetcher::builder()
+            .avail_url(&network.avail_url)
+            .avail_chain_id(&network.avail_chain_id)
+            .request_timeout(SUBSCRIPTION_TIMEOUT)
+            .justification_store(store.clone())
+            .build()
+            .await
+        {
+            Ok(fetcher) => {
+                let fetcher = Arc::new(fetcher);
 
-        // Initialize the PostgreSQL client.
-        let postgres_client = match PostgresClient::new().await {
-            Ok(client) => client,
-            Err(_) => {
-                error!("Failed to initialize PostgreSQL client");
-                continue;
-            }
-        };
+                if let Some(policy) = &retention_policy {
+                    if tasks
+                        .pruner
+                        .as_ref()
+                        .is_none_or(|handle| handle.is_finished())
+                    {
+                        let mut rows_pruned = Arc::default();
+                        update_health(&health, &avail_chain_id, |network_health| {
+                            rows_pruned = network_health.rows_pruned.clone();
+                        });
+                        let task = Pruner::new(fetcher.clone(), store.clone(), policy.clone())
+                            .with_rows_removed(rows_pruned);
+                        tasks.pruner = Some(tokio::spawn(task.run()));
+                    }
+                }
+
+                match initialize_subscription(&fetcher).await {
+                    Ok(mut sub) => {
+                        debug!(
+                            "Subscription for chain {} initialized successfully",
+                            avail_chain_id
+                        );
+                        update_health(&health, &avail_chain_id, |network_health| {
+                            network_health.subscribed = true;
+                        });
 
-        match initialize_subscription(&fetcher).await {
-            Ok(mut sub) => {
-                debug!("Subscription initialized successfully");
-                handle_subscription(&mut sub, &postgres_client, &fetcher, timeout_duration).await;
+                        if tasks
+                            .catch_up
+                            .as_ref()
+                            .is_none_or(|handle| handle.is_finished())
+                        {
+                            tasks.catch_up = spawn_catch_up(fetcher.clone(), store.clone()).await;
+                        } else {
+                            debug!(
+                                "Previous catch-up for chain {} is still running",
+                                avail_chain_id
+                            );
+                        }
+
+                        received =
+                            handle_subscription(&mut sub, store.as_ref(), &fetcher, &health).await;
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to initialize subscription for chain {}: {:?}",
+                            avail_chain_id, e
+                        );
+                    }
+                }
             }
             Err(e) => {
-                debug!("Failed to initialize subscription: {:?}", e);
+                error!(
+                    "Failed to initialize fetcher for chain {}: {:?}",
+                    avail_chain_id, e
+                );
             }
         }
 
-        debug!("Retrying subscription in {} seconds", retry_delay.as_secs());
+        // Back off while the network keeps failing without yielding any justification.
+        if received {
+            retry_delay = INITIAL_RETRY_DELAY;
+        }
+        update_health(&health, &avail_chain_id, |network_health| {
+            network_health.subscribed = false;
+            if !received {
+                network_health.consecutive_failures += 1;
+            }
+        });
+
+        debug!(
+            "Retrying subscription for chain {} in {} seconds",
+            avail_chain_id,
+            retry_delay.as_secs()
+        );
         tokio::time::sleep(retry_delay).await;
+        if !received {
+            retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+        }
     }
 }
 
+/// Listen for justifications on every configured network, with one supervised task per network.
+/// A network task that stops is restarted, and the health of every network is reported
+/// periodically.
+async fn listen_for_justifications() {
+    let networks = NetworkConfig::fetch().expect("No networks configured");
+    let retention_policy = RetentionPolicy::from_env().expect("Invalid retention policy");
+
+    // The justification store is shared by every network.
+    let mut retry_delay = INITIAL_RETRY_DELAY;
+    let store = loop {
+        match store::connect_from_env().await {
+            Ok(store) => break store,
+            Err(e) => {
+                error!("Failed to initialize the justification store: {:?}", e);
+                tokio::time::sleep(retry_delay).await;
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    };
+    spawn_legacy_conversion(store.clone());
+
+    let health = HealthRegistry::default();
+    let spawn_network = |network: &NetworkConfig| {
+        tokio::spawn(index_network(
+            network.clone(),
+            store.clone(),
+            retention_policy.clone(),
+            health.clone(),
+        ))
+    };
+
+    info!(
+        "Indexing justifications for chains: {:?}",
+        networks
+            .iter()
+            .map(|network| network.avail_chain_id.as_str())
+            .collect::<Vec<_>>()
+    );
+    let mut tasks: Vec<(NetworkConfig, JoinHandle<()>)> = networks
+        .iter()
+        .map(|network| (network.clone(), spawn_network(network)))
+        .collect();
+
+    let mut interval = tokio::time::interval(HEALTH_REPORT_INTERVAL);
+    // The first tick completes immediately, before any network has connected.
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+
+        for (network, task) in tasks.iter_mut() {
+            if task.is_finished() {
+                error!(
+                    "Indexing task for chain {} stopped, restarting",
+                    network.avail_chain_id
+                );
+                *task = spawn_network(network);
+            }
+        }
+        report_health(&health);
+    }
+}
+
+/// Backfill the justifications missing from the indexer DB in [from, to] inclusive.
+async fn backfill(from: u32, to: u32, concurrency: usize) {
+    let fetcher = RpcDataFetcher::new().await;
+    let store = store::connect_from_env()
+        .await
+        .expect("Failed to initialize the justification store");
+
+    match backfill_justifications(&fetcher, store.as_ref(), from, to, concurrency).await {
+        Ok(stats) => info!(
+            "Backfill from block {} to block {} complete in {:?}: {} missing blocks, {} justifications added, {} rejected, {} failed requests",
+            from,
+            to,
+            stats.duration,
+            stats.missing_blocks,
+            stats.added,
+            stats.rejected,
+            stats.failed_requests
+        ),
+        Err(e) => error!("Backfill from block {} to block {} failed: {:?}", from, to, e),
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Index Avail justifications into PostgreSQL or SQLite.")]
+struct IndexerArgs {
+    #[command(subcommand)]
+    command: Option<IndexerCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum IndexerCommand {
+    /// Listen for new justifications (default).
+    Listen,
+    /// Backfill the justifications missing from the DB for a historical block range.
+    Backfill {
+        /// First block of the range, inclusive.
+        #[arg(long)]
+        from: u32,
+        /// Last block of the range, inclusive.
+        #[arg(long)]
+        to: u32,
+        /// Maximum number of concurrent finality proof requests.
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+    },
+}
+
 #[tokio::main]
 pub async fn main() {
     dotenv::dotenv().ok();
     env_logger::init();
 
-    listen_for_justifications().await;
+    let args = IndexerArgs::parse();
+
+    match args.command.unwrap_or(IndexerCommand::Listen) {
+        IndexerCommand::Listen => listen_for_justifications().await,
+        IndexerCommand::Backfill {
+            from,
+            to,
+            concurrency,
+        } => backfill(from, to, concurrency).await,
+    }
 }
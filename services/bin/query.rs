@@ -0,0 +1,359 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::primitives::{Address, B256};
+use alloy::providers::ProviderBuilder;
+use anyhow::Result;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use services::contract::{get_block_range, get_health, rpc_url_for_chain, Deployment};
+use services::data_root::{get_data_root_proof, DataRootProof};
+use services::input::{RpcDataFetcher, VectorXQueryResponse};
+use services::store::{self, JustificationStore};
+use tracing::{error, info};
+
+/// The default number of hours to search for `HeadUpdate` logs in `/api/health`.
+const DEFAULT_MAX_DELAY_HOURS: u64 = 4;
+
+/// Timeout for connecting to the Avail RPC.
+const AVAIL_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct AppState {
+    store: Arc<dyn JustificationStore>,
+    deployments: Vec<Deployment>,
+}
+
+/// Connect to the Avail chain with the given name, at the `AVAIL_WS_{NAME}` environment variable.
+async fn avail_fetcher(chain_name: &str) -> Result<RpcDataFetcher> {
+    let avail_url = std::env::var(format!("AVAIL_WS_{}", chain_name.to_uppercase()))
+        .map_err(|_| anyhow::anyhow!("Chain name {} is not supported", chain_name))?;
+
+    RpcDataFetcher::builder()
+        .avail_url(avail_url)
+        .avail_chain_id(chain_name.to_lowercase())
+        .request_timeout(AVAIL_CONNECT_TIMEOUT)
+        .build()
+        .await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JustificationParams {
+    block_number: Option<u32>,
+    avail_chain_id: Option<String>,
+}
+
+/// Get the justification for a given Avail block.
+/// - blockNumber: The block number of the Avail block.
+/// - availChainId: The Avail chain id the justification was indexed under.
+async fn justification(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<JustificationParams>,
+) -> Json<VectorXQueryResponse> {
+    let (Some(block_number), Some(avail_chain_id)) = (params.block_number, params.avail_chain_id)
+    else {
+        return Json(VectorXQueryResponse {
+            success: false,
+            justification: None,
+            error: Some("Missing required parameters: blockNumber and availChainId".to_string()),
+        });
+    };
+
+    let error = match state
+        .store
+        .get_justification(&avail_chain_id, block_number)
+        .await
+    {
+        Ok(justification) => {
+            return Json(VectorXQueryResponse {
+                success: true,
+                justification: Some(justification),
+                error: None,
+            })
+        }
+        Err(e) => match state
+            .store
+            .justification_exists(&avail_chain_id, block_number)
+            .await
+        {
+            Ok(false) => "No justification found",
+            _ => {
+                error!("Database error: {:?}", e);
+                "Database error occurred"
+            }
+        },
+    };
+
+    Json(VectorXQueryResponse {
+        success: false,
+        justification: None,
+        error: Some(error.to_string()),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RangeParams {
+    contract_chain_id: Option<u64>,
+    contract_address: Option<Address>,
+}
+
+/// Get the range of blocks that the VectorX contract has emitted logs for.
+/// - contractChainId: The chain ID where the VectorX contract is deployed.
+/// - contractAddress: The address of the VectorX contract.
+async fn range(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RangeParams>,
+) -> Json<Value> {
+    let (Some(chain_id), Some(contract_address)) =
+        (params.contract_chain_id, params.contract_address)
+    else {
+        return Json(json!({ "success": false }));
+    };
+
+    let Some(deployment) = Deployment::find(&state.deployments, chain_id, contract_address) else {
+        return Json(json!({
+            "success": false,
+            "error": "Failed to get block range for requested block! This means that the specified contract is not registered in this service."
+        }));
+    };
+
+    let result = async {
+        let provider = ProviderBuilder::new().on_http(rpc_url_for_chain(chain_id)?.parse()?);
+        get_block_range(&provider, deployment).await
+    }
+    .await;
+
+    match result {
+        Ok(range) => Json(json!({ "data": range })),
+        Err(e) => {
+            error!("Failed to get block range: {:?}", e);
+            Json(json!({ "success": false, "error": e.to_string() }))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HealthParams {
+    chain_name: Option<String>,
+    contract_chain_id: Option<u64>,
+    contract_address: Option<Address>,
+    max_delay_hours: Option<u64>,
+}
+
+/// Request the health of a VectorX light client. Searches for the latest log emitted by the VectorX
+/// contract and compares the contract's latest block to the latest block on the Avail chain.
+/// - chainName: The name of the Avail chain to check.
+/// - contractChainId: The chain ID where the VectorX contract is deployed.
+/// - contractAddress: The address of the VectorX contract.
+/// - maxDelayHours: The number of hours to check for emitted logs. Default is 4 hours.
+async fn health(Query(params): Query<HealthParams>) -> Json<Value> {
+    let (Some(chain_name), Some(chain_id), Some(contract_address)) = (
+        params.chain_name,
+        params.contract_chain_id,
+        params.contract_address,
+    ) else {
+        return Json(json!({ "success": false, "error": "Missing required parameters" }));
+    };
+    let max_delay_hours = params.max_delay_hours.unwrap_or(DEFAULT_MAX_DELAY_HOURS);
+
+    let result = async {
+        let rpc_url = rpc_url_for_chain(chain_id)?;
+        let fetcher = avail_fetcher(&chain_name).await?;
+        let avail_head_block = fetcher.get_head().await.number;
+
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+        get_health(
+            &provider,
+            contract_address,
+            avail_head_block,
+            max_delay_hours * 60 * 60,
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(health) => Json(json!({ "data": health })),
+        Err(e) => {
+            error!("Failed to get health: {:?}", e);
+            Json(json!({ "success": false, "error": e.to_string() }))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataRootProofParams {
+    chain_name: Option<String>,
+    contract_chain_id: Option<u64>,
+    contract_address: Option<Address>,
+    block_hash: Option<B256>,
+    block_number: Option<u32>,
+}
+
+/// An Avail block, by number or by hash.
+#[derive(Debug, Clone, Copy)]
+enum AvailBlock {
+    Number(u32),
+    Hash(B256),
+}
+
+impl AvailBlock {
+    /// The block hash if it is set, as in the TypeScript route, or else the block number.
+    fn from_params(block_hash: Option<B256>, block_number: Option<u32>) -> Option<Self> {
+        block_hash
+            .map(AvailBlock::Hash)
+            .or(block_number.map(AvailBlock::Number))
+    }
+}
+
+/// Get the proof that an Avail block's data root is included in a data commitment stored by a
+/// VectorX contract.
+/// - chainName: The name of the Avail chain.
+/// - contractChainId: The chain ID where the VectorX contract is deployed.
+/// - contractAddress: The address of the VectorX contract.
+/// - blockHash | blockNumber: The block hash or block number of the Avail block.
+async fn data_root_proof(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DataRootProofParams>,
+) -> Json<Value> {
+    let (Some(chain_name), Some(chain_id), Some(contract_address), Some(block)) = (
+        params.chain_name,
+        params.contract_chain_id,
+        params.contract_address,
+        AvailBlock::from_params(params.block_hash, params.block_number),
+    ) else {
+        return Json(json!({ "success": false, "error": "Missing required parameters" }));
+    };
+
+    let Some(deployment) = Deployment::find(&state.deployments, chain_id, contract_address) else {
+        return Json(json!({
+            "success": false,
+            "error": "The specified contract is not registered in this service."
+        }));
+    };
+
+    match prove_data_root(&chain_name, deployment, block).await {
+        Ok(proof) => Json(json!({ "data": proof })),
+        Err(e) => {
+            error!("Failed to get data root proof: {:?}", e);
+            Json(json!({ "success": false, "error": e.to_string() }))
+        }
+    }
+}
+
+/// Get the data root proof of an Avail block from a contract deployment. A block requested by hash
+/// must be on the canonical chain.
+async fn prove_data_root(
+    chain_name: &str,
+    deployment: &Deployment,
+    block: AvailBlock,
+) -> Result<DataRootProof> {
+    let provider =
+        ProviderBuilder::new().on_http(rpc_url_for_chain(deployment.contract_chain_id)?.parse()?);
+    let fetcher = avail_fetcher(chain_name).await?;
+
+    let block_number = match block {
+        AvailBlock::Number(block_number) => block_number,
+        AvailBlock::Hash(block_hash) => fetcher.get_header_by_hash(block_hash).await?.number,
+    };
+    let proof = get_data_root_proof(&provider, &fetcher, deployment, block_number).await?;
+
+    if let AvailBlock::Hash(block_hash) = block {
+        if proof.block_hash != block_hash {
+            return Err(anyhow::anyhow!(
+                "Block {} is not on the canonical chain",
+                block_hash
+            ));
+        }
+    }
+
+    Ok(proof)
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Serve the VectorX query API from the justification store.")]
+struct QueryArgs {
+    /// The port to listen on.
+    #[arg(long, default_value_t = 3000)]
+    port: u16,
+    /// The contract deployments served by `/api/range` and `/api`.
+    #[arg(long, default_value = "deployments.json")]
+    deployments: String,
+    #[command(subcommand)]
+    command: Option<QueryCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum QueryCommand {
+    /// Serve the query API (default).
+    Serve,
+    /// Print the proof that an Avail block's data root is included in a data commitment stored by
+    /// a contract, as JSON.
+    DataRootProof {
+        /// The name of the Avail chain, with its RPC at `AVAIL_WS_{NAME}`.
+        #[arg(long)]
+        chain_name: String,
+        /// The chain ID where the contract is deployed, with its RPC at `RPC_{ID}`.
+        #[arg(long)]
+        contract_chain_id: u64,
+        #[arg(long)]
+        contract_address: Address,
+        /// The Avail block number to prove.
+        #[arg(long, required_unless_present = "block_hash")]
+        block_number: Option<u32>,
+        /// The Avail block hash to prove, instead of the block number.
+        #[arg(long, conflicts_with = "block_number")]
+        block_hash: Option<B256>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    env_logger::init();
+
+    let args = QueryArgs::parse();
+
+    if let Some(QueryCommand::DataRootProof {
+        chain_name,
+        contract_chain_id,
+        contract_address,
+        block_number,
+        block_hash,
+    }) = args.command
+    {
+        let deployments = Deployment::from_file(&args.deployments)?;
+        let deployment = Deployment::find(&deployments, contract_chain_id, contract_address)
+            .ok_or_else(|| anyhow::anyhow!("Contract not found in {}", args.deployments))?;
+        let block = AvailBlock::from_params(block_hash, block_number)
+            .ok_or_else(|| anyhow::anyhow!("No block hash or block number provided"))?;
+        let proof = prove_data_root(&chain_name, deployment, block).await?;
+        println!("{}", serde_json::to_string_pretty(&proof)?);
+        return Ok(());
+    }
+
+    let state = Arc::new(AppState {
+        store: store::connect_from_env().await?,
+        deployments: Deployment::from_file(&args.deployments)?,
+    });
+
+    let app = Router::new()
+        .route("/api", get(data_root_proof))
+        .route("/api/justification", get(justification))
+        .route("/api/range", get(range))
+        .route("/api/health", get(health))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port)).await?;
+    info!("Query server listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
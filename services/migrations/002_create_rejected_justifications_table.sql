@@ -0,0 +1,14 @@
+-- Migration: Create rejected_justifications table for services
+-- Records justifications that failed verification in the indexer, with the reason they were rejected
+
+CREATE TABLE IF NOT EXISTS rejected_justifications (
+    id SERIAL PRIMARY KEY,
+    avail_chain_id VARCHAR(100) NOT NULL,
+    block_number INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX IF NOT EXISTS idx_rejected_justifications_avail_chain_block ON rejected_justifications(avail_chain_id, block_number);
@@ -0,0 +1,6 @@
+-- Migration: Add a SCALE-encoded column to the justifications table
+-- Reads prefer the encoded form, which is much smaller and faster to decode than the JSON form. The JSON
+-- data column is still written for the TypeScript query service. Existing rows are converted in the
+-- background by the indexer.
+
+ALTER TABLE justifications ADD COLUMN IF NOT EXISTS encoded BYTEA;
@@ -0,0 +1,15 @@
+-- Migration: Create justifications_archive table for services
+-- Justifications pruned by the indexer's retention policy are moved here when it runs in archive mode
+
+CREATE TABLE IF NOT EXISTS justifications_archive (
+    id VARCHAR(255) PRIMARY KEY,
+    avail_chain_id VARCHAR(100) NOT NULL,
+    block_number INTEGER NOT NULL,
+    data JSONB NOT NULL,
+    encoded BYTEA,
+    created_at TIMESTAMP WITH TIME ZONE,
+    archived_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX IF NOT EXISTS idx_justifications_archive_avail_chain_block ON justifications_archive(avail_chain_id, block_number);
@@ -0,0 +1,21 @@
+-- Migration: Create authority_sets table for services
+-- Records each GRANDPA authority set, from the ScheduledChange and ForcedChange digests seen by the indexer
+
+CREATE TABLE IF NOT EXISTS authority_sets (
+    avail_chain_id VARCHAR(100) NOT NULL,
+    authority_set_id BIGINT NOT NULL,
+    -- Concatenated 32 byte ed25519 public keys of the authorities.
+    authorities BYTEA NOT NULL,
+    authority_set_hash BYTEA NOT NULL,
+    -- The epoch end block of the previous set, which introduced this set.
+    start_block INTEGER,
+    -- The last block attested to by this set. NULL while the set is active.
+    epoch_end_block INTEGER,
+    -- 'scheduled' or 'forced'.
+    change_type VARCHAR(20),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (avail_chain_id, authority_set_id)
+);
+
+-- Create indexes for better query performance
+CREATE INDEX IF NOT EXISTS idx_authority_sets_avail_chain_epoch_end ON authority_sets(avail_chain_id, epoch_end_block);
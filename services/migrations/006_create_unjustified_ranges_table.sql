@@ -0,0 +1,14 @@
+-- Migration: Create unjustified_ranges table for services
+-- Records the block ranges the Avail node has no justification for, so the backfill skips them on later runs
+
+CREATE TABLE IF NOT EXISTS unjustified_ranges (
+    avail_chain_id VARCHAR(100) NOT NULL,
+    start_block INTEGER NOT NULL,
+    -- Inclusive.
+    end_block INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (avail_chain_id, start_block)
+);
+
+-- Create indexes for better query performance
+CREATE INDEX IF NOT EXISTS idx_unjustified_ranges_avail_chain_end ON unjustified_ranges(avail_chain_id, end_block);
@@ -0,0 +1,250 @@
+//! Record the GRANDPA authority set changes seen by the indexer.
+
+use alloy::primitives::B256;
+use anyhow::{Context, Result};
+use avail_subxt::config::substrate::DigestItem;
+use avail_subxt::primitives::grandpa::ConsensusLog;
+use avail_subxt::primitives::Header;
+use codec::Decode;
+use sp1_vector_primitives::compute_authority_set_commitment;
+use tracing::info;
+
+use crate::input::RpcDataFetcher;
+use crate::store::JustificationStore;
+use crate::types::{AuthoritySet, AuthoritySetChange};
+
+/// Find the GRANDPA authority set change digest in a header, if any.
+pub fn find_authority_set_change(header: &Header) -> Option<AuthoritySetChange> {
+    header.digest.logs.iter().find_map(|log| match log {
+        DigestItem::Consensus([b'F', b'R', b'N', b'K'], data) => {
+            match ConsensusLog::<u32>::decode(&mut data.as_slice()) {
+                Ok(ConsensusLog::ScheduledChange(_)) => Some(AuthoritySetChange::Scheduled),
+                Ok(ConsensusLog::ForcedChange(_, _)) => Some(AuthoritySetChange::Forced),
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+/// If the header carries a GRANDPA authority set change digest, record the end of the current
+/// authority set and the next authority set it introduces. Returns the id of the next authority
+/// set, if any.
+pub async fn record_authority_set_change(
+    fetcher: &RpcDataFetcher,
+    store: &dyn JustificationStore,
+    header: &Header,
+) -> Result<Option<u64>> {
+    let block_number = header.number;
+    if block_number == 0 {
+        return Ok(None);
+    }
+
+    let Some(change_type) = find_authority_set_change(header) else {
+        return Ok(None);
+    };
+
+    // The authorities defined in a block attest to the next block, so the current set is defined in
+    // the previous block, and the next set in the epoch end block.
+    let authority_set_id = fetcher
+        .get_authority_set_id(block_number - 1)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to get the authority set id at block {}",
+                block_number - 1
+            )
+        })?;
+    let authorities = fetcher
+        .get_authorities(block_number - 1)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to get the authorities at block {}",
+                block_number - 1
+            )
+        })?;
+    let next_authorities = fetcher
+        .get_authorities(block_number)
+        .await
+        .with_context(|| format!("Failed to get the authorities at block {}", block_number))?;
+
+    store_authority_set_change(
+        store,
+        &fetcher.avail_chain_id,
+        block_number,
+        change_type,
+        authority_set_id,
+        authorities,
+        next_authorities,
+    )
+    .await?;
+
+    Ok(Some(authority_set_id + 1))
+}
+
+/// Record the end of authority set `authority_set_id` at `epoch_end_block`, and the start of the next
+/// authority set.
+async fn store_authority_set_change(
+    store: &dyn JustificationStore,
+    avail_chain_id: &str,
+    epoch_end_block: u32,
+    change_type: AuthoritySetChange,
+    authority_set_id: u64,
+    authorities: Vec<B256>,
+    next_authorities: Vec<B256>,
+) -> Result<()> {
+    store
+        .add_authority_set(
+            avail_chain_id,
+            &AuthoritySet {
+                authority_set_id,
+                authority_set_hash: compute_authority_set_commitment(&authorities),
+                authorities,
+                start_block: None,
+                epoch_end_block: Some(epoch_end_block),
+                change_type: None,
+            },
+        )
+        .await?;
+    store
+        .add_authority_set(
+            avail_chain_id,
+            &AuthoritySet {
+                authority_set_id: authority_set_id + 1,
+                authority_set_hash: compute_authority_set_commitment(&next_authorities),
+                authorities: next_authorities,
+                start_block: Some(epoch_end_block),
+                epoch_end_block: None,
+                change_type: Some(change_type),
+            },
+        )
+        .await?;
+
+    info!(
+        "Recorded {} authority set change for chain {} at block {}: authority set {} -> {}",
+        change_type.as_str(),
+        avail_chain_id,
+        epoch_end_block,
+        authority_set_id,
+        authority_set_id + 1
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use codec::Encode;
+
+    use super::*;
+    use crate::sqlite::SqliteClient;
+
+    const GRANDPA_ENGINE_ID: [u8; 4] = *b"FRNK";
+
+    /// A header with the given digest logs, in the JSON format of the test assets.
+    fn test_header(logs: Vec<DigestItem>) -> Header {
+        let mut header: Header = serde_json::from_value(serde_json::json!({
+            "parentHash": format!("0x{}", "00".repeat(32)),
+            "number": "2",
+            "stateRoot": format!("0x{}", "00".repeat(32)),
+            "extrinsicsRoot": format!("0x{}", "00".repeat(32)),
+            "digest": { "logs": [] },
+            "extension": {
+                "V3": {
+                    "appLookup": { "size": 0, "index": [] },
+                    "commitment": {
+                        "rows": 0,
+                        "cols": 0,
+                        "commitment": [],
+                        "dataRoot": format!("0x{}", "00".repeat(32))
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        header.digest.logs = logs;
+        header
+    }
+
+    /// A GRANDPA consensus log, SCALE encoded as `ConsensusLog::<u32>` with the given variant index
+    /// and fields.
+    fn grandpa_log(index: u8, fields: &[u8]) -> DigestItem {
+        let mut data = vec![index];
+        data.extend_from_slice(fields);
+        DigestItem::Consensus(GRANDPA_ENGINE_ID, data)
+    }
+
+    /// The encoded `ScheduledChange` of one authority with a delay of 0.
+    fn scheduled_change() -> Vec<u8> {
+        let next_authorities: Vec<([u8; 32], u64)> = vec![([1u8; 32], 1)];
+        (next_authorities, 0u32).encode()
+    }
+
+    #[test]
+    fn test_find_authority_set_change() {
+        // ConsensusLog::ScheduledChange.
+        let header = test_header(vec![grandpa_log(1, &scheduled_change())]);
+        assert_eq!(
+            find_authority_set_change(&header),
+            Some(AuthoritySetChange::Scheduled)
+        );
+
+        // ConsensusLog::ForcedChange, with the median of the last finalized block.
+        let mut forced_change = 5u32.encode();
+        forced_change.extend(scheduled_change());
+        let header = test_header(vec![grandpa_log(2, &forced_change)]);
+        assert_eq!(
+            find_authority_set_change(&header),
+            Some(AuthoritySetChange::Forced)
+        );
+
+        // No digest, another GRANDPA log (ConsensusLog::OnDisabled), or a log of another engine.
+        assert_eq!(find_authority_set_change(&test_header(vec![])), None);
+        let header = test_header(vec![grandpa_log(3, &0u64.encode())]);
+        assert_eq!(find_authority_set_change(&header), None);
+        let header = test_header(vec![DigestItem::Consensus(*b"BABE", scheduled_change())]);
+        assert_eq!(find_authority_set_change(&header), None);
+    }
+
+    #[tokio::test]
+    async fn test_store_authority_set_change() {
+        let store = SqliteClient::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-chain";
+
+        let authorities = vec![B256::repeat_byte(1), B256::repeat_byte(2)];
+        let next_authorities = vec![B256::repeat_byte(3)];
+        store_authority_set_change(
+            &store,
+            chain_id,
+            200,
+            AuthoritySetChange::Forced,
+            5,
+            authorities.clone(),
+            next_authorities.clone(),
+        )
+        .await
+        .unwrap();
+
+        let current = store.get_authority_set(chain_id, 5).await.unwrap().unwrap();
+        assert_eq!(current.authorities, authorities);
+        assert_eq!(
+            current.authority_set_hash,
+            compute_authority_set_commitment(&authorities)
+        );
+        assert_eq!(current.epoch_end_block, Some(200));
+        assert_eq!(current.start_block, None);
+
+        let next = store.get_authority_set(chain_id, 6).await.unwrap().unwrap();
+        assert_eq!(next.authorities, next_authorities);
+        assert_eq!(next.start_block, Some(200));
+        assert_eq!(next.epoch_end_block, None);
+        assert_eq!(next.change_type, Some(AuthoritySetChange::Forced));
+        assert_eq!(
+            store.get_latest_authority_set(chain_id).await.unwrap(),
+            Some(next)
+        );
+    }
+}
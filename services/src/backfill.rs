@@ -0,0 +1,317 @@
+//! Backfill justifications for historical block ranges that are missing from the indexer DB.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures::future::join_all;
+use tracing::{debug, info, warn};
+
+use crate::authority_set::record_authority_set_change;
+use crate::input::RpcDataFetcher;
+use crate::store::JustificationStore;
+
+/// Log the progress of each backfill worker after this many requests.
+const PROGRESS_LOG_INTERVAL: usize = 100;
+
+/// Summary of a completed backfill.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BackfillStats {
+    /// The number of blocks in the range without a justification when the backfill started,
+    /// excluding the blocks the Avail node is known to have no justification for.
+    pub missing_blocks: usize,
+    /// The number of justifications that were verified and added to the DB.
+    pub added: usize,
+    /// The number of justifications that failed verification.
+    pub rejected: usize,
+    /// The number of `grandpa_proveFinality` requests that failed.
+    pub failed_requests: usize,
+    pub duration: Duration,
+}
+
+/// Backfill the justifications for the blocks in [start_block, end_block] inclusive that are missing
+/// from the DB, with up to `concurrency` concurrent workers.
+///
+/// The Avail node only stores justifications for some blocks, so `grandpa_proveFinality` returns the
+/// justification of the first block >= the requested block that has one. Each justification is
+/// verified before it is stored, and the workers skip past every missing block up to the justified
+/// block. The blocks skipped this way are recorded as unjustified, so later backfills don't request
+/// them again, and justifications for blocks after `end_block` are ignored. The missing blocks are
+/// read from the DB when the backfill starts, so an interrupted backfill resumes from where it
+/// stopped when it is re-run.
+pub async fn backfill_justifications(
+    fetcher: &RpcDataFetcher,
+    store: &dyn JustificationStore,
+    start_block: u32,
+    end_block: u32,
+    concurrency: usize,
+) -> Result<BackfillStats> {
+    if start_block > end_block {
+        return Err(anyhow::anyhow!(
+            "Start block {} is after end block {}",
+            start_block,
+            end_block
+        ));
+    }
+    if concurrency == 0 {
+        return Err(anyhow::anyhow!("Concurrency must be greater than 0"));
+    }
+
+    let start_time = Instant::now();
+    let missing_blocks = store
+        .get_missing_block_numbers(&fetcher.avail_chain_id, start_block, end_block)
+        .await?;
+    let unjustified_ranges = store
+        .get_unjustified_ranges(&fetcher.avail_chain_id, start_block, end_block)
+        .await?;
+    let missing_blocks = skip_unjustified_blocks(missing_blocks, &unjustified_ranges);
+
+    info!(
+        "Backfilling {} missing blocks between {} and {} for chain {}",
+        missing_blocks.len(),
+        start_block,
+        end_block,
+        fetcher.avail_chain_id
+    );
+
+    let mut stats = BackfillStats {
+        missing_blocks: missing_blocks.len(),
+        ..Default::default()
+    };
+    if missing_blocks.is_empty() {
+        return Ok(stats);
+    }
+
+    // Split the missing blocks into contiguous chunks, one for each worker. The justification for
+    // the end of a chunk can be in the next chunk, so the justified blocks are claimed by the first
+    // worker to see them.
+    let claimed_blocks = Mutex::new(HashSet::new());
+    let worker_stats = join_all(
+        chunk_missing_blocks(&missing_blocks, concurrency)
+            .enumerate()
+            .map(|(worker, chunk)| {
+                backfill_chunk(fetcher, store, worker, chunk, end_block, &claimed_blocks)
+            }),
+    )
+    .await;
+
+    for worker_stats in worker_stats {
+        stats.added += worker_stats.added;
+        stats.rejected += worker_stats.rejected;
+        stats.failed_requests += worker_stats.failed_requests;
+    }
+    stats.duration = start_time.elapsed();
+
+    Ok(stats)
+}
+
+/// Remove the blocks in the inclusive unjustified ranges from the sorted missing blocks.
+fn skip_unjustified_blocks(
+    missing_blocks: Vec<u32>,
+    unjustified_ranges: &[(u32, u32)],
+) -> Vec<u32> {
+    missing_blocks
+        .into_iter()
+        .filter(|&block| {
+            !unjustified_ranges
+                .iter()
+                .any(|&(start, end)| start <= block && block <= end)
+        })
+        .collect()
+}
+
+/// Split the sorted missing blocks into at most `concurrency` contiguous chunks.
+fn chunk_missing_blocks(missing_blocks: &[u32], concurrency: usize) -> std::slice::Chunks<'_, u32> {
+    missing_blocks.chunks(missing_blocks.len().div_ceil(concurrency).max(1))
+}
+
+/// The index of the next block to request after the request at `index` returned the justification
+/// for `justified_block`. The node has no justification for the blocks before the justified block,
+/// so all of them are skipped.
+fn next_missing_index(missing_blocks: &[u32], index: usize, justified_block: u32) -> usize {
+    missing_blocks
+        .partition_point(|&block| block <= justified_block)
+        .max(index + 1)
+}
+
+/// Record that the node has no justification for [start_block, end_block], logging any error.
+async fn record_unjustified_range(
+    fetcher: &RpcDataFetcher,
+    store: &dyn JustificationStore,
+    start_block: u32,
+    end_block: u32,
+) {
+    if let Err(e) = store
+        .add_unjustified_range(&fetcher.avail_chain_id, start_block, end_block)
+        .await
+    {
+        warn!(
+            "Failed to record blocks {} to {} as unjustified: {:?}",
+            start_block, end_block, e
+        );
+    }
+}
+
+/// Backfill a sorted chunk of missing blocks sequentially. Justifications for blocks after
+/// `end_block`, or for blocks in `claimed_blocks`, are not verified or stored.
+async fn backfill_chunk(
+    fetcher: &RpcDataFetcher,
+    store: &dyn JustificationStore,
+    worker: usize,
+    missing_blocks: &[u32],
+    end_block: u32,
+    claimed_blocks: &Mutex<HashSet<u32>>,
+) -> BackfillStats {
+    let mut stats = BackfillStats::default();
+    let mut index = 0;
+    let mut num_requests = 0;
+
+    while index < missing_blocks.len() {
+        let block_number = missing_blocks[index];
+        num_requests += 1;
+        if num_requests % PROGRESS_LOG_INTERVAL == 0 {
+            info!(
+                "Backfill worker {} at block {} ({}/{} blocks)",
+                worker,
+                block_number,
+                index,
+                missing_blocks.len()
+            );
+        }
+
+        let justification = match fetcher
+            .get_justification_data_for_block_unsafe(block_number)
+            .await
+        {
+            Ok(justification) => justification,
+            Err(e) => {
+                warn!(
+                    "Failed to get finality proof for block {}: {:?}",
+                    block_number, e
+                );
+                stats.failed_requests += 1;
+                index += 1;
+                continue;
+            }
+        };
+
+        let justified_block = justification.commit.target_number;
+        debug!(
+            "Finality proof for block {} is justified at block {}",
+            block_number, justified_block
+        );
+
+        if justified_block > end_block {
+            // None of the remaining blocks of the chunk have a justification.
+            debug!(
+                "Justified block {} is after the end of the backfill at block {}, ignoring it",
+                justified_block, end_block
+            );
+            record_unjustified_range(fetcher, store, block_number, end_block).await;
+            break;
+        }
+
+        if !claimed_blocks.lock().unwrap().insert(justified_block) {
+            debug!(
+                "Justification for block {} was already backfilled by another worker",
+                justified_block
+            );
+            if block_number < justified_block {
+                record_unjustified_range(fetcher, store, block_number, justified_block - 1).await;
+            }
+            index = next_missing_index(missing_blocks, index, justified_block);
+            continue;
+        }
+
+        match fetcher.verify_grandpa_justification(&justification).await {
+            Ok(header) => {
+                if let Err(e) = store
+                    .add_justification(&fetcher.avail_chain_id, justification)
+                    .await
+                {
+                    warn!(
+                        "Failed to add justification for block {}: {:?}",
+                        justified_block, e
+                    );
+                } else {
+                    stats.added += 1;
+                }
+                if let Err(e) = record_authority_set_change(fetcher, store, &header).await {
+                    warn!(
+                        "Failed to record authority set change at block {}: {:?}",
+                        justified_block, e
+                    );
+                }
+                if block_number < justified_block {
+                    record_unjustified_range(fetcher, store, block_number, justified_block - 1)
+                        .await;
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "Rejected justification for block {}: {:?}",
+                    justified_block, e
+                );
+                stats.rejected += 1;
+                if let Err(e) = store
+                    .add_rejected_justification(
+                        &fetcher.avail_chain_id,
+                        &justification,
+                        &e.to_string(),
+                    )
+                    .await
+                {
+                    warn!(
+                        "Failed to record rejected justification for block {}: {:?}",
+                        justified_block, e
+                    );
+                }
+            }
+        }
+
+        index = next_missing_index(missing_blocks, index, justified_block);
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_unjustified_blocks() {
+        let missing_blocks = vec![1, 2, 3, 5, 8, 9, 10, 12];
+        assert_eq!(
+            skip_unjustified_blocks(missing_blocks.clone(), &[]),
+            missing_blocks
+        );
+        assert_eq!(
+            skip_unjustified_blocks(missing_blocks, &[(2, 5), (9, 9), (11, 20)]),
+            vec![1, 8, 10]
+        );
+    }
+
+    #[test]
+    fn test_chunk_missing_blocks() {
+        let missing_blocks: Vec<u32> = (0..10).collect();
+        let chunks: Vec<&[u32]> = chunk_missing_blocks(&missing_blocks, 3).collect();
+        assert_eq!(chunks, vec![&[0, 1, 2, 3][..], &[4, 5, 6, 7], &[8, 9]]);
+
+        // More workers than blocks.
+        let chunks: Vec<&[u32]> = chunk_missing_blocks(&missing_blocks[..2], 4).collect();
+        assert_eq!(chunks, vec![&[0][..], &[1]]);
+    }
+
+    #[test]
+    fn test_next_missing_index() {
+        let missing_blocks = [10, 11, 12, 20, 21, 30];
+        // Every missing block up to the justified block is skipped.
+        assert_eq!(next_missing_index(&missing_blocks, 0, 20), 4);
+        assert_eq!(next_missing_index(&missing_blocks, 0, 25), 5);
+        assert_eq!(next_missing_index(&missing_blocks, 0, 40), 6);
+        // A justification before the requested block still moves past it.
+        assert_eq!(next_missing_index(&missing_blocks, 3, 15), 4);
+    }
+}
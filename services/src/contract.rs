@@ -0,0 +1,265 @@
+//! Queries of the SP1Vector contract deployed on destination chains.
+
+use std::cmp::Ordering;
+use std::env;
+
+use alloy::consensus::BlockHeader;
+use alloy::eips::BlockId;
+use alloy::network::BlockResponse;
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::rpc::types::Filter;
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    contract SP1Vector {
+        uint32 public latestBlock;
+
+        event HeadUpdate(uint32 blockNumber, bytes32 headerHash);
+        event HeaderRangeCommitmentStored(
+            uint32 startBlock,
+            uint32 endBlock,
+            bytes32 dataCommitment,
+            bytes32 stateCommitment,
+            uint32 headerRangeCommitmentTreeSize
+        );
+    }
+}
+
+/// The number of blocks to query logs for in a single request.
+pub const LOG_QUERY_BATCH_SIZE: u64 = 10_000;
+
+/// An SP1Vector contract deployment.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployment {
+    pub contract_chain_id: u64,
+    pub contract_address: Address,
+    /// The block to start searching for the contract's logs from.
+    pub cursor_start_block: u64,
+    pub avail_chain_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeploymentConfig {
+    deployments: Vec<Deployment>,
+}
+
+impl Deployment {
+    /// Read the deployments from a JSON file, in the same format as the query service's
+    /// `deployments.json`.
+    pub fn from_file(path: &str) -> Result<Vec<Self>> {
+        let file = std::fs::read_to_string(path)?;
+        let config: DeploymentConfig = serde_json::from_str(&file)?;
+
+        Ok(config.deployments)
+    }
+
+    /// Find the deployment of a contract.
+    pub fn find(deployments: &[Self], chain_id: u64, contract_address: Address) -> Option<&Self> {
+        deployments.iter().find(|deployment| {
+            deployment.contract_chain_id == chain_id
+                && deployment.contract_address == contract_address
+        })
+    }
+}
+
+/// Get the RPC URL of a destination chain from the `RPC_{chain_id}` environment variable.
+pub fn rpc_url_for_chain(chain_id: u64) -> Result<String> {
+    env::var(format!("RPC_{}", chain_id))
+        .map_err(|_| anyhow::anyhow!("Missing RPC URL for chain {}", chain_id))
+}
+
+/// Query the logs of an event emitted by a contract in [from_block, to_block] inclusive, in batches
+/// of [`LOG_QUERY_BATCH_SIZE`] blocks. Returns the block number of each log with the decoded event,
+/// in ascending order.
+pub async fn query_logs<E: SolEvent>(
+    provider: &impl Provider,
+    contract_address: Address,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<(u64, E)>> {
+    let mut events = Vec::new();
+    let mut batch_start = from_block;
+
+    while batch_start <= to_block {
+        let batch_end = (batch_start + LOG_QUERY_BATCH_SIZE - 1).min(to_block);
+        let filter = Filter::new()
+            .from_block(batch_start)
+            .to_block(batch_end)
+            .address(contract_address)
+            .event_signature(E::SIGNATURE_HASH);
+
+        for log in provider.get_logs(&filter).await? {
+            let block_number = log
+                .block_number
+                .ok_or_else(|| anyhow::anyhow!("Log is missing its block number"))?;
+            events.push((block_number, log.log_decode::<E>()?.inner.data));
+        }
+
+        batch_start = batch_end + 1;
+    }
+
+    Ok(events)
+}
+
+/// The range of Avail blocks committed to by a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RangeInfo {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Get the range of Avail blocks a contract has stored data commitments for, from the first and
+/// last `HeaderRangeCommitmentStored` logs.
+pub async fn get_block_range(
+    provider: &impl Provider,
+    deployment: &Deployment,
+) -> Result<RangeInfo> {
+    let latest_block = provider.get_block_number().await?;
+    let contract_address = deployment.contract_address;
+
+    // Find the first data commitment log after the cursor start block.
+    let mut cursor = deployment.cursor_start_block;
+    let start = loop {
+        if cursor > latest_block {
+            return Err(anyhow::anyhow!("No data commitment logs found"));
+        }
+        let logs = query_logs::<SP1Vector::HeaderRangeCommitmentStored>(
+            provider,
+            contract_address,
+            cursor,
+            (cursor + LOG_QUERY_BATCH_SIZE).min(latest_block),
+        )
+        .await?;
+        match logs.first() {
+            // The start block in Avail is one block ahead of the event start block.
+            Some((_, event)) => break event.startBlock + 1,
+            None => cursor += LOG_QUERY_BATCH_SIZE + 1,
+        }
+    };
+
+    // Search for the most recent data commitment log, backwards from the latest block.
+    let mut cursor = latest_block;
+    let end = loop {
+        let batch_start = cursor
+            .saturating_sub(LOG_QUERY_BATCH_SIZE)
+            .max(deployment.cursor_start_block);
+        let logs = query_logs::<SP1Vector::HeaderRangeCommitmentStored>(
+            provider,
+            contract_address,
+            batch_start,
+            cursor,
+        )
+        .await?;
+        match logs.last() {
+            Some((_, event)) => break event.endBlock,
+            None if batch_start == deployment.cursor_start_block => {
+                return Err(anyhow::anyhow!("No data commitment logs found"));
+            }
+            None => cursor = batch_start - 1,
+        }
+    };
+
+    Ok(RangeInfo { start, end })
+}
+
+/// The health of a contract, in the same format as the query service's `/api/health`.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthInfo {
+    /// The number of Avail blocks between the finalized head and the contract's latest block.
+    pub blocks_behind_head: i64,
+    pub eth_blocks_since_last_log: u64,
+    pub last_log_timestamp: u64,
+    /// Whether a `HeadUpdate` log was emitted in the search window, 10 times `max_delay_seconds`.
+    pub log_emitted: bool,
+}
+
+/// Get the health of a contract, by comparing its latest block to the Avail finalized head and
+/// searching for its latest `HeadUpdate` log.
+pub async fn get_health(
+    provider: &impl Provider,
+    contract_address: Address,
+    avail_head_block: u32,
+    max_delay_seconds: u64,
+) -> Result<HealthInfo> {
+    let Some(current_block) = provider.get_block(BlockId::latest()).await? else {
+        return Err(anyhow::anyhow!("No latest block found"));
+    };
+    let current_block_number = current_block.header().number();
+    let current_block_timestamp = current_block.header().timestamp();
+
+    // Search for logs in 10 times the number of blocks in the max delay.
+    let query_block = find_block_before_timestamp(
+        provider,
+        current_block_timestamp.saturating_sub(max_delay_seconds),
+    )
+    .await?;
+    let diff_blocks = current_block_number - query_block;
+    let search_start_block = current_block_number.saturating_sub(diff_blocks * 10);
+
+    let logs = query_logs::<SP1Vector::HeadUpdate>(
+        provider,
+        contract_address,
+        search_start_block,
+        current_block_number,
+    )
+    .await?;
+    let last_log_block = logs
+        .last()
+        .map_or(search_start_block, |(block_number, _)| *block_number);
+
+    let latest_block = SP1Vector::new(contract_address, provider)
+        .latestBlock()
+        .call()
+        .await?;
+
+    let Some(last_log_block_data) = provider.get_block(last_log_block.into()).await? else {
+        return Err(anyhow::anyhow!("No block found"));
+    };
+
+    Ok(HealthInfo {
+        blocks_behind_head: avail_head_block as i64 - latest_block as i64,
+        eth_blocks_since_last_log: current_block_number - last_log_block,
+        last_log_timestamp: last_log_block_data.header().timestamp(),
+        log_emitted: !logs.is_empty(),
+    })
+}
+
+/// Find the number of the most recent block with a timestamp before the target timestamp.
+async fn find_block_before_timestamp(
+    provider: &impl Provider,
+    target_timestamp: u64,
+) -> Result<u64> {
+    let mut low = 0;
+    let mut high = provider.get_block_number().await?;
+    let mut found = 0;
+
+    while low <= high {
+        let mid = low + (high - low) / 2;
+        let Some(block) = provider.get_block(mid.into()).await? else {
+            return Err(anyhow::anyhow!("No block found"));
+        };
+
+        match block.header().timestamp().cmp(&target_timestamp) {
+            Ordering::Less => {
+                found = mid;
+                low = mid + 1;
+            }
+            Ordering::Equal | Ordering::Greater => {
+                if mid == 0 {
+                    break;
+                }
+                high = mid - 1;
+            }
+        }
+    }
+
+    Ok(found)
+}
@@ -0,0 +1,240 @@
+//! Inclusion proofs of Avail data roots in the data commitments stored by the SP1Vector contract.
+
+use alloy::primitives::{keccak256, B256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolValue;
+use anyhow::Result;
+use codec::Encode;
+use serde::Serialize;
+use sp1_vector_primitives::header_range::decode_header;
+use sp1_vector_primitives::merkle::{
+    get_merkle_branch, get_merkle_root_commitments, get_merkle_root_from_branch,
+};
+use sp1_vector_primitives::types::DecodedHeaderData;
+
+use crate::contract::{query_logs, Deployment, SP1Vector, LOG_QUERY_BATCH_SIZE};
+use crate::input::RpcDataFetcher;
+
+/// A proof that an Avail block's data root is included in the data commitment stored under
+/// `dataRootCommitments[range_hash]`. Serialized in the same format as the query service's `/api`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRootProof {
+    pub block_number: u32,
+    pub block_hash: B256,
+    pub data_root: B256,
+    /// The key of the data commitment in the contract, `keccak256(abi.encode(startBlock, endBlock))`.
+    pub range_hash: B256,
+    pub data_commitment: B256,
+    /// The sibling nodes from the data root up to the data commitment.
+    pub merkle_branch: Vec<B256>,
+    /// The index of the data root in the tree.
+    pub index: u32,
+    /// The number of leaves in the tree, including the padding.
+    pub total_leaves: u32,
+}
+
+/// The outcome of searching a batch of `HeaderRangeCommitmentStored` events for an Avail block.
+enum RangeSearch {
+    /// The event whose range contains the block.
+    Found(SP1Vector::HeaderRangeCommitmentStored),
+    /// A range ending before the block was committed, and none contains it, so the block has not
+    /// been committed yet.
+    NotCommitted,
+    /// The block may be in an earlier batch.
+    Continue,
+}
+
+/// Search a batch of `HeaderRangeCommitmentStored` events for the range containing the Avail block.
+fn search_range_commitments(
+    events: impl IntoIterator<Item = SP1Vector::HeaderRangeCommitmentStored>,
+    block_number: u32,
+) -> RangeSearch {
+    let mut committed_before_block = false;
+    for event in events {
+        // The block after the event start block is the first block of the range.
+        if event.startBlock < block_number && block_number <= event.endBlock {
+            return RangeSearch::Found(event);
+        }
+        // Ranges are committed in ascending order, so if a later range ends before the block, the
+        // block has not been committed yet.
+        committed_before_block |= event.endBlock < block_number;
+    }
+
+    if committed_before_block {
+        RangeSearch::NotCommitted
+    } else {
+        RangeSearch::Continue
+    }
+}
+
+/// The batches of destination chain blocks to query for logs, from `latest_block` down to
+/// `start_block`, both inclusive.
+fn log_batches(latest_block: u64, start_block: u64) -> impl Iterator<Item = (u64, u64)> {
+    let mut cursor = (latest_block >= start_block).then_some(latest_block);
+    std::iter::from_fn(move || {
+        let batch_end = cursor?;
+        let batch_start = (batch_end + 1)
+            .saturating_sub(LOG_QUERY_BATCH_SIZE)
+            .max(start_block);
+        cursor = (batch_start > start_block).then(|| batch_start - 1);
+        Some((batch_start, batch_end))
+    })
+}
+
+/// Find the `HeaderRangeCommitmentStored` event whose range contains the Avail block, searching
+/// backwards from the latest block of the destination chain.
+async fn find_range_commitment(
+    provider: &impl Provider,
+    deployment: &Deployment,
+    block_number: u32,
+) -> Result<SP1Vector::HeaderRangeCommitmentStored> {
+    let latest_block = provider.get_block_number().await?;
+    for (batch_start, batch_end) in log_batches(latest_block, deployment.cursor_start_block) {
+        let logs = query_logs::<SP1Vector::HeaderRangeCommitmentStored>(
+            provider,
+            deployment.contract_address,
+            batch_start,
+            batch_end,
+        )
+        .await?;
+
+        match search_range_commitments(logs.into_iter().map(|(_, event)| event), block_number) {
+            RangeSearch::Found(event) => return Ok(event),
+            RangeSearch::NotCommitted => {
+                return Err(anyhow::anyhow!(
+                    "Block {} has not been committed to by the contract yet",
+                    block_number
+                ))
+            }
+            RangeSearch::Continue => {}
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "No data commitment found for block {}",
+        block_number
+    ))
+}
+
+/// Get the proof that the data root of an Avail block is included in the data commitment stored by
+/// a contract. The tree is rebuilt from the headers of the committed range and checked against the
+/// commitments in the `HeaderRangeCommitmentStored` event.
+pub async fn get_data_root_proof(
+    provider: &impl Provider,
+    fetcher: &RpcDataFetcher,
+    deployment: &Deployment,
+    block_number: u32,
+) -> Result<DataRootProof> {
+    let event = find_range_commitment(provider, deployment, block_number).await?;
+    let tree_size = event.headerRangeCommitmentTreeSize as usize;
+    if !tree_size.is_power_of_two() || tree_size < (event.endBlock - event.startBlock) as usize {
+        return Err(anyhow::anyhow!(
+            "Invalid tree size {} for range [{}, {}]",
+            tree_size,
+            event.startBlock,
+            event.endBlock
+        ));
+    }
+
+    let headers = fetcher
+        .get_block_headers_range(event.startBlock + 1, event.endBlock)
+        .await?;
+    let decoded_headers: Vec<DecodedHeaderData> = headers
+        .iter()
+        .map(|header| decode_header(&header.encode()))
+        .collect();
+
+    let (state_commitment, data_commitment) =
+        get_merkle_root_commitments(&decoded_headers, tree_size);
+    if state_commitment != event.stateCommitment || data_commitment != event.dataCommitment {
+        return Err(anyhow::anyhow!(
+            "Commitments rebuilt from the headers of range [{}, {}] do not match the contract",
+            event.startBlock,
+            event.endBlock
+        ));
+    }
+
+    let index = (block_number - event.startBlock - 1) as usize;
+    let leaves: Vec<B256> = decoded_headers
+        .iter()
+        .map(|header| header.data_root)
+        .collect();
+    let merkle_branch = get_merkle_branch(&leaves, tree_size, index);
+    let header = &decoded_headers[index];
+    if get_merkle_root_from_branch(header.data_root, &merkle_branch, index) != data_commitment {
+        return Err(anyhow::anyhow!(
+            "Merkle branch of block {} does not match the data commitment",
+            block_number
+        ));
+    }
+
+    Ok(DataRootProof {
+        block_number,
+        block_hash: header.header_hash,
+        data_root: header.data_root,
+        range_hash: keccak256((event.startBlock, event.endBlock).abi_encode_params()),
+        data_commitment,
+        merkle_branch,
+        index: index as u32,
+        total_leaves: tree_size as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start_block: u32, end_block: u32) -> SP1Vector::HeaderRangeCommitmentStored {
+        SP1Vector::HeaderRangeCommitmentStored {
+            startBlock: start_block,
+            endBlock: end_block,
+            dataCommitment: B256::ZERO,
+            stateCommitment: B256::ZERO,
+            headerRangeCommitmentTreeSize: 1024,
+        }
+    }
+
+    #[test]
+    fn test_search_range_commitments() {
+        let events = || vec![event(100, 200), event(200, 300)];
+
+        // The start block of a range is not part of it.
+        assert!(matches!(
+            search_range_commitments(events(), 200),
+            RangeSearch::Found(e) if e.startBlock == 100
+        ));
+        assert!(matches!(
+            search_range_commitments(events(), 201),
+            RangeSearch::Found(e) if e.startBlock == 200
+        ));
+        assert!(matches!(
+            search_range_commitments(events(), 301),
+            RangeSearch::NotCommitted
+        ));
+        assert!(matches!(
+            search_range_commitments(events(), 100),
+            RangeSearch::Continue
+        ));
+        assert!(matches!(
+            search_range_commitments(vec![], 100),
+            RangeSearch::Continue
+        ));
+    }
+
+    #[test]
+    fn test_log_batches() {
+        let batches: Vec<_> = log_batches(25_000, 3_000).collect();
+        assert_eq!(
+            batches,
+            vec![(15_001, 25_000), (5_001, 15_000), (3_000, 5_000)]
+        );
+
+        // The search stops at the start block.
+        assert_eq!(
+            log_batches(3_000, 3_000).collect::<Vec<_>>(),
+            vec![(3_000, 3_000)]
+        );
+        assert_eq!(log_batches(2_999, 3_000).count(), 0);
+    }
+}
@@ -8,9 +8,15 @@ use sp1_vector_primitives::{compute_authority_set_commitment, consts::HASH_SIZE}
 use sp_core::H256;
 use std::cmp::Ordering;
 use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use subxt::backend::rpc::RpcSubscription;
 
-use crate::types::{EncodedFinalityProof, FinalityProof, GrandpaJustification};
+use crate::store::{self, JustificationStore};
+use crate::types::{AuthoritySet, EncodedFinalityProof, FinalityProof, GrandpaJustification};
+use crate::verify::{check_header_range_inputs, check_justification, check_rotate_inputs};
+use crate::Timeout;
 use alloy::primitives::{B256, B512};
 use avail_subxt::avail_client::AvailClient;
 use avail_subxt::config::substrate::DigestItem;
@@ -18,19 +24,193 @@ use avail_subxt::primitives::Header;
 use avail_subxt::{api, RpcParams};
 use codec::{Compact, Decode, Encode};
 use futures::future::join_all;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sp_core::ed25519;
 
-/// In order to avoid errors from the RPC client, tasks should coordinate via this mutex to coordinate
-/// large amounts of concurrent requests.
-static CONCURRENCY_MUTEX: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// The default maximum number of concurrent requests on the Avail WS connection. The WS connection
+/// will error if there are too many concurrent requests with Rpc(ClientError(MaxSlotsExceeded)).
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 200;
 
-/// An RPC data fetcher for fetching data for VectorX. The vectorx_query_url is only necessary when
-/// querying justifications.
+/// The default timeout for connecting to the Avail RPC and for requests to the vectorx-query service.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An RPC data fetcher for fetching data for VectorX. The vectorx_query_url and justification_store
+/// are only necessary when querying justifications, depending on the justification source.
 pub struct RpcDataFetcher {
     pub client: AvailClient,
     pub avail_chain_id: String,
     pub vectorx_query_url: Option<String>,
+    pub justification_source: JustificationSource,
+    pub justification_store: Option<Arc<dyn JustificationStore>>,
+    /// HTTP client for the vectorx-query service.
+    query_client: reqwest::Client,
+    /// The maximum number of concurrent requests on the Avail WS connection.
+    max_concurrent_requests: usize,
+    /// In order to avoid errors from the RPC client, tasks should coordinate via this mutex to
+    /// coordinate large amounts of concurrent requests.
+    concurrency_mutex: tokio::sync::Mutex<()>,
+}
+
+/// Builder for an [`RpcDataFetcher`] with explicit configuration, independent of the environment.
+pub struct RpcDataFetcherBuilder {
+    avail_url: Option<String>,
+    avail_chain_id: Option<String>,
+    vectorx_query_url: Option<String>,
+    database_url: Option<String>,
+    justification_store: Option<Arc<dyn JustificationStore>>,
+    justification_source: JustificationSource,
+    request_timeout: Duration,
+    max_concurrent_requests: usize,
+}
+
+impl Default for RpcDataFetcherBuilder {
+    fn default() -> Self {
+        Self {
+            avail_url: None,
+            avail_chain_id: None,
+            vectorx_query_url: None,
+            database_url: None,
+            justification_store: None,
+            justification_source: JustificationSource::default(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+impl RpcDataFetcherBuilder {
+    /// The Avail RPC endpoint. Required.
+    pub fn avail_url(mut self, avail_url: impl Into<String>) -> Self {
+        self.avail_url = Some(avail_url.into());
+        self
+    }
+
+    /// The Avail chain id justifications are stored under (e.g. `mainnet`, `turing`). Required.
+    pub fn avail_chain_id(mut self, avail_chain_id: impl Into<String>) -> Self {
+        self.avail_chain_id = Some(avail_chain_id.into());
+        self
+    }
+
+    /// The vectorx-query service endpoint. Required for the query service justification sources.
+    pub fn vectorx_query_url(mut self, vectorx_query_url: impl Into<String>) -> Self {
+        self.vectorx_query_url = Some(vectorx_query_url.into());
+        self
+    }
+
+    /// The justification indexer database, `postgres://` or `sqlite:`. Required for the database
+    /// justification sources, unless a store is passed with [`Self::justification_store`].
+    pub fn database_url(mut self, database_url: impl Into<String>) -> Self {
+        self.database_url = Some(database_url.into());
+        self
+    }
+
+    /// An existing connection to the justification indexer database.
+    pub fn justification_store(mut self, justification_store: Arc<dyn JustificationStore>) -> Self {
+        self.justification_store = Some(justification_store);
+        self
+    }
+
+    pub fn justification_source(mut self, justification_source: JustificationSource) -> Self {
+        self.justification_source = justification_source;
+        self
+    }
+
+    /// Timeout for connecting to the Avail RPC and for requests to the vectorx-query service.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// The maximum number of concurrent requests on the Avail WS connection.
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = max_concurrent_requests;
+        self
+    }
+
+    /// Connect to the configured endpoints and build the fetcher.
+    pub async fn build(self) -> Result<RpcDataFetcher> {
+        let avail_url = self
+            .avail_url
+            .ok_or_else(|| anyhow::anyhow!("Avail URL must be set"))?;
+        let avail_chain_id = self
+            .avail_chain_id
+            .ok_or_else(|| anyhow::anyhow!("Avail chain id must be set"))?;
+        if self.max_concurrent_requests == 0 {
+            return Err(anyhow::anyhow!(
+                "Max concurrent requests must be greater than 0"
+            ));
+        }
+
+        let client = AvailClient::new(avail_url.as_str())
+            .timeout(self.request_timeout)
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out connecting to {}", avail_url))?
+            .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {:?}", avail_url, e))?;
+
+        // Only connect to the database if justifications are read from it.
+        let justification_store = match (self.justification_store, self.database_url) {
+            (Some(justification_store), _) => Some(justification_store),
+            (None, Some(database_url)) if self.justification_source.uses_database() => {
+                Some(store::connect(&database_url).await?)
+            }
+            (None, _) if self.justification_source.uses_database() => {
+                return Err(anyhow::anyhow!(
+                    "Database URL must be set for justification source {:?}",
+                    self.justification_source
+                ));
+            }
+            (None, _) => None,
+        };
+
+        let query_client = reqwest::Client::builder()
+            .timeout(self.request_timeout)
+            .build()?;
+
+        Ok(RpcDataFetcher {
+            client,
+            avail_chain_id,
+            vectorx_query_url: self.vectorx_query_url,
+            justification_source: self.justification_source,
+            justification_store,
+            query_client,
+            max_concurrent_requests: self.max_concurrent_requests,
+            concurrency_mutex: tokio::sync::Mutex::new(()),
+        })
+    }
+}
+
+/// Where justifications for non epoch end blocks are read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JustificationSource {
+    /// The vectorx-query HTTP service at `VECTORX_QUERY_URL`.
+    #[default]
+    QueryService,
+    /// The justification indexer's database at `DATABASE_URL`.
+    Database,
+    /// The justification indexer's database, falling back to the vectorx-query HTTP service on
+    /// failure.
+    DatabaseWithFallback,
+}
+
+impl JustificationSource {
+    /// Whether this source reads from the justification indexer's database.
+    pub fn uses_database(&self) -> bool {
+        matches!(self, Self::Database | Self::DatabaseWithFallback)
+    }
+}
+
+impl FromStr for JustificationSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "query" => Self::QueryService,
+            // `postgres` is accepted for compatibility with existing configurations.
+            "database" | "postgres" => Self::Database,
+            "database-with-fallback" | "postgres-with-fallback" => Self::DatabaseWithFallback,
+            _ => return Err(anyhow::anyhow!("Invalid justification source: {}", s)),
+        })
+    }
 }
 
 /// Data for the header range request.
@@ -41,30 +221,105 @@ pub struct HeaderRangeRequestData {
     pub is_target_epoch_end_block: bool,
 }
 
-#[derive(Debug, Deserialize)]
+/// The result of searching a block range for a block with an available justification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JustificationDiscovery {
+    /// The first block in the range with an available justification, if any.
+    pub justified_block: Option<u32>,
+    /// Whether the justified block was found with `grandpa_proveFinality` instead of the indexer.
+    pub from_finality_proof: bool,
+    /// Ranges of blocks, inclusive, that are missing from the justification indexer.
+    pub gaps: Vec<(u32, u32)>,
+}
+
+/// The response of the query service's `/api/justification` endpoint. Unset fields are omitted, as
+/// in the TypeScript query service.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct VectorXQueryResponse {
     pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub justification: Option<GrandpaJustification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
 impl RpcDataFetcher {
+    pub fn builder() -> RpcDataFetcherBuilder {
+        RpcDataFetcherBuilder::default()
+    }
+
+    /// Build a fetcher from the `AVAIL_URL`, `AVAIL_CHAIN_ID`, `VECTORX_QUERY_URL`,
+    /// `JUSTIFICATION_SOURCE` and `DATABASE_URL` environment variables.
     pub async fn new() -> Self {
         dotenv::dotenv().ok();
 
         let url = env::var("AVAIL_URL").expect("AVAIL_URL must be set");
-        let client = AvailClient::new(url.as_str()).await.unwrap();
         let avail_chain_id = env::var("AVAIL_CHAIN_ID").expect("AVAIL_CHAIN_ID must be set");
-        let vectorx_query_url = env::var("VECTORX_QUERY_URL").ok();
-        RpcDataFetcher {
-            client,
-            avail_chain_id,
-            vectorx_query_url,
+        let justification_source: JustificationSource = env::var("JUSTIFICATION_SOURCE")
+            .map(|v| v.parse().unwrap())
+            .unwrap_or_default();
+
+        let mut builder = Self::builder()
+            .avail_url(url)
+            .avail_chain_id(avail_chain_id)
+            .justification_source(justification_source);
+        if let Ok(vectorx_query_url) = env::var("VECTORX_QUERY_URL") {
+            builder = builder.vectorx_query_url(vectorx_query_url);
         }
+        if justification_source.uses_database() {
+            builder =
+                builder.database_url(env::var("DATABASE_URL").expect("DATABASE_URL must be set"));
+        }
+
+        builder.build().await.unwrap()
     }
 
-    /// Gets a justification from the vectorx-query service, which reads the data from postgres database.
+    /// Gets a justification from the configured justification source.
     pub async fn get_justification(&self, block_number: u32) -> Result<GrandpaJustification> {
+        match self.justification_source {
+            JustificationSource::QueryService => {
+                self.get_justification_from_query_service(block_number)
+                    .await
+            }
+            JustificationSource::Database => {
+                self.get_justification_from_database(block_number).await
+            }
+            JustificationSource::DatabaseWithFallback => {
+                match self.get_justification_from_database(block_number).await {
+                    Ok(justification) => Ok(justification),
+                    Err(e) => {
+                        tracing::debug!(
+                            "Failed to read justification for block {} from the database, falling back to the query service: {:?}",
+                            block_number,
+                            e
+                        );
+                        self.get_justification_from_query_service(block_number)
+                            .await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets a justification directly from the justification indexer's database.
+    pub async fn get_justification_from_database(
+        &self,
+        block_number: u32,
+    ) -> Result<GrandpaJustification> {
+        let Some(justification_store) = self.justification_store.as_ref() else {
+            return Err(anyhow::anyhow!("DATABASE_URL must be set"));
+        };
+
+        justification_store
+            .get_justification(&self.avail_chain_id, block_number)
+            .await
+    }
+
+    /// Gets a justification from the vectorx-query service, which reads the data from postgres database.
+    pub async fn get_justification_from_query_service(
+        &self,
+        block_number: u32,
+    ) -> Result<GrandpaJustification> {
         if self.vectorx_query_url.is_none() {
             return Err(anyhow::anyhow!("VECTORX_QUERY_URL must be set"));
         }
@@ -79,7 +334,7 @@ impl RpcDataFetcher {
             base_justification_query_url, self.avail_chain_id, block_number
         );
 
-        let response = reqwest::get(request_url).await?;
+        let response = self.query_client.get(request_url).send().await?;
         let json_response = response.json::<VectorXQueryResponse>().await?;
 
         let is_success = json_response.success;
@@ -94,20 +349,43 @@ impl RpcDataFetcher {
 
     /// Get the inputs for a header range proof. Optionally pass in the header range commitment tree size.
     /// If not passed in, it will be set to the nearest power of 2.
+    ///
+    /// The inputs are verified natively with the same checks as the SP1 Vector program, and an error
+    /// is returned if they would not produce a valid proof.
     pub async fn get_header_range_inputs(
         &self,
         header_range_request_data: HeaderRangeRequestData,
         header_range_commitment_tree_size: Option<u32>,
-    ) -> HeaderRangeInputs {
+    ) -> Result<HeaderRangeInputs> {
+        let header_range_inputs = self
+            .fetch_header_range_inputs(header_range_request_data, header_range_commitment_tree_size)
+            .await?;
+
+        check_header_range_inputs(&header_range_inputs, header_range_request_data)?;
+
+        Ok(header_range_inputs)
+    }
+
+    /// Fetch the inputs for a header range proof like [`Self::get_header_range_inputs`], without
+    /// verifying them.
+    pub async fn fetch_header_range_inputs(
+        &self,
+        header_range_request_data: HeaderRangeRequestData,
+        header_range_commitment_tree_size: Option<u32>,
+    ) -> Result<HeaderRangeInputs> {
         let num_headers =
             header_range_request_data.target_block - header_range_request_data.trusted_block + 1;
         let merkle_tree_size: usize;
         if let Some(header_range_commitment_tree_size) = header_range_commitment_tree_size {
-            assert!(
-                header_range_commitment_tree_size >= num_headers
-                    && header_range_commitment_tree_size.is_power_of_two(),
-                "Header range commitment tree size must be greater than or equal to the number of headers and a power of two"
-            );
+            if header_range_commitment_tree_size < num_headers
+                || !header_range_commitment_tree_size.is_power_of_two()
+            {
+                return Err(anyhow::anyhow!(
+                    "Header range commitment tree size {} must be greater than or equal to the number of headers {} and a power of two",
+                    header_range_commitment_tree_size,
+                    num_headers
+                ));
+            }
             merkle_tree_size = header_range_commitment_tree_size as usize;
         } else {
             // NOTE: DANGEROUS. ONLY USED IN TESTING. IN PROD, FETCH FROM CONTRACT.
@@ -125,7 +403,7 @@ impl RpcDataFetcher {
                 header_range_request_data.trusted_block,
                 header_range_request_data.target_block,
             )
-            .await;
+            .await?;
         let encoded_headers: Vec<Vec<u8>> = headers.iter().map(|header| header.encode()).collect();
 
         let target_justification = self
@@ -134,32 +412,70 @@ impl RpcDataFetcher {
                 header_range_request_data.is_target_epoch_end_block,
             )
             .await
-            .expect("Failed to get justification data for target block.");
-
-        HeaderRangeInputs {
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Failed to get justification data for target block {}",
+                    header_range_request_data.target_block
+                )
+            })?;
+
+        Ok(HeaderRangeInputs {
             merkle_tree_size,
             encoded_headers,
             target_justification,
-        }
+        })
+    }
+
+    /// Get the inputs for a rotate proof from the given authority set to the next authority set.
+    ///
+    /// The inputs are verified natively with the same checks as the SP1 Vector program, and an error
+    /// is returned if they would not produce a valid proof.
+    pub async fn get_rotate_inputs(&self, authority_set_id: u64) -> Result<RotateInputs> {
+        let rotate_inputs = self.fetch_rotate_inputs(authority_set_id).await?;
+
+        check_rotate_inputs(&rotate_inputs, authority_set_id)?;
+
+        Ok(rotate_inputs)
     }
 
-    pub async fn get_rotate_inputs(&self, authority_set_id: u64) -> RotateInputs {
+    /// Fetch the inputs for a rotate proof like [`Self::get_rotate_inputs`], without verifying them.
+    pub async fn fetch_rotate_inputs(&self, authority_set_id: u64) -> Result<RotateInputs> {
         let justification = self
             .get_justification_data_epoch_end_block(authority_set_id)
-            .await;
+            .await?;
 
-        let header_rotate_data = self.get_header_rotate(authority_set_id).await;
+        let header_rotate_data = self.get_header_rotate(authority_set_id).await?;
 
-        RotateInputs {
+        Ok(RotateInputs {
             justification,
             header_rotate_data,
-        }
+        })
     }
 
     // This function returns the last block justified by target_authority_set_id. This block
     // also specifies the new authority set, which starts justifying after this block.
     // Returns 0 if curr_authority_set_id <= target_authority_set_id.
-    pub async fn last_justified_block(&self, target_authority_set_id: u64) -> u32 {
+    //
+    // The authority sets recorded by the indexer are checked first, if there is a justification store.
+    pub async fn last_justified_block(&self, target_authority_set_id: u64) -> Result<u32> {
+        if let Some(justification_store) = self.justification_store.as_ref() {
+            match justification_store
+                .get_authority_set(&self.avail_chain_id, target_authority_set_id)
+                .await
+            {
+                Ok(Some(AuthoritySet {
+                    epoch_end_block: Some(epoch_end_block),
+                    ..
+                })) => return Ok(epoch_end_block),
+                Ok(_) => {}
+                Err(e) => tracing::debug!(
+                    "Failed to read authority set {} from the store: {:?}",
+                    target_authority_set_id,
+                    e
+                ),
+            }
+        }
+
         let mut low = 0;
         let head_block = self.get_head().await;
         let mut high = head_block.number;
@@ -167,7 +483,7 @@ impl RpcDataFetcher {
 
         while low <= high {
             let mid = (low + high) / 2;
-            let mid_authority_set_id = self.get_authority_set_id(mid).await;
+            let mid_authority_set_id = self.get_authority_set_id(mid).await?;
 
             match mid_authority_set_id.cmp(&(target_authority_set_id + 1)) {
                 Ordering::Equal => {
@@ -176,7 +492,7 @@ impl RpcDataFetcher {
                         epoch_end_block_number = mid;
                         break;
                     }
-                    let prev_authority_set_id = self.get_authority_set_id(mid - 1).await;
+                    let prev_authority_set_id = self.get_authority_set_id(mid - 1).await?;
                     if prev_authority_set_id == target_authority_set_id {
                         epoch_end_block_number = mid;
                         break;
@@ -188,17 +504,32 @@ impl RpcDataFetcher {
                 Ordering::Greater => high = mid - 1,
             }
         }
-        epoch_end_block_number
+        Ok(epoch_end_block_number)
+    }
+
+    /// The last block justified by the authority set, like [`Self::last_justified_block`]. Returns an
+    /// error if the authority set is still active, so it has no epoch end block yet.
+    async fn epoch_end_block(&self, authority_set_id: u64) -> Result<u32> {
+        match self.last_justified_block(authority_set_id).await? {
+            0 => Err(anyhow::anyhow!(
+                "Authority set {} is still active",
+                authority_set_id
+            )),
+            epoch_end_block => Ok(epoch_end_block),
+        }
     }
 
-    pub async fn get_block_hash(&self, block_number: u32) -> B256 {
+    /// Get the hash of a block. Returns an error if the block is unknown to the node, as for a future
+    /// block.
+    pub async fn get_block_hash(&self, block_number: u32) -> Result<B256> {
         let block_hash = self
             .client
             .legacy_rpc()
             .chain_get_block_hash(Some(block_number.into()))
-            .await;
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Block {} not found", block_number))?;
 
-        B256::from(block_hash.unwrap().unwrap().0)
+        Ok(B256::from(block_hash.0))
     }
 
     /// This function returns a vector of headers for a given range of block numbers, inclusive of the start and end block numbers.
@@ -206,42 +537,47 @@ impl RpcDataFetcher {
         &self,
         start_block_number: u32,
         end_block_number: u32,
-    ) -> Vec<Header> {
-        // Fetch the headers in batches of MAX_CONCURRENT_WS_REQUESTS. The WS connection will error if there
+    ) -> Result<Vec<Header>> {
+        // Fetch the headers in batches of max_concurrent_requests. The WS connection will error if there
         // are too many concurrent requests with Rpc(ClientError(MaxSlotsExceeded)).
-        const MAX_CONCURRENT_WS_REQUESTS: usize = 200;
+        let max_concurrent_requests = self.max_concurrent_requests as u32;
 
         // Take the guard to coordinate concurrent requests.
-        let _guard = CONCURRENCY_MUTEX.lock().await;
+        let _guard = self.concurrency_mutex.lock().await;
 
         let mut headers = Vec::new();
         let mut curr_block = start_block_number;
         while curr_block <= end_block_number {
-            let end_block = std::cmp::min(
-                curr_block + MAX_CONCURRENT_WS_REQUESTS as u32 - 1,
-                end_block_number,
-            );
+            let end_block =
+                std::cmp::min(curr_block + max_concurrent_requests - 1, end_block_number);
             let header_futures: Vec<_> = (curr_block..end_block + 1)
                 .map(|block_number| self.get_header(block_number))
                 .collect();
 
             // Await all futures concurrently
-            let headers_batch: Vec<Header> = join_all(header_futures).await;
+            let headers_batch = join_all(header_futures)
+                .await
+                .into_iter()
+                .collect::<Result<Vec<Header>>>()?;
 
             headers.extend_from_slice(&headers_batch);
-            curr_block += MAX_CONCURRENT_WS_REQUESTS as u32;
+            curr_block += max_concurrent_requests;
         }
-        headers
+        Ok(headers)
     }
 
-    pub async fn get_header(&self, block_number: u32) -> Header {
-        let block_hash = self.get_block_hash(block_number).await;
-        let header_result = self
-            .client
+    pub async fn get_header(&self, block_number: u32) -> Result<Header> {
+        let block_hash = self.get_block_hash(block_number).await?;
+        self.get_header_by_hash(block_hash).await
+    }
+
+    /// Get the header of a block by its hash. Returns an error if the block is unknown to the node.
+    pub async fn get_header_by_hash(&self, block_hash: B256) -> Result<Header> {
+        self.client
             .legacy_rpc()
             .chain_get_header(Some(H256::from(block_hash.0)))
-            .await;
-        header_result.unwrap().unwrap()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Block {} not found", block_hash))
     }
 
     pub async fn get_head(&self) -> Header {
@@ -259,62 +595,64 @@ impl RpcDataFetcher {
         header.unwrap().unwrap()
     }
 
-    pub async fn get_authority_set_id(&self, block_number: u32) -> u64 {
-        let block_hash = self.get_block_hash(block_number).await;
+    pub async fn get_authority_set_id(&self, block_number: u32) -> Result<u64> {
+        let block_hash = self.get_block_hash(block_number).await?;
 
         let set_id_key = api::storage().grandpa().current_set_id();
         self.client
             .storage()
             .at(H256::from(block_hash.0))
             .fetch(&set_id_key)
-            .await
-            .unwrap()
-            .unwrap()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No authority set id stored at block {}", block_number))
     }
 
     // This function returns the authorities (as AffinePoint and public key bytes) for a given block number
     // by fetching the "authorities_bytes" from storage and decoding the bytes to a VersionedAuthorityList.
     // Note: The authorities returned by this function attest to block_number + 1.
-    pub async fn get_authorities(&self, block_number: u32) -> Vec<B256> {
-        let block_hash = self.get_block_hash(block_number).await;
+    pub async fn get_authorities(&self, block_number: u32) -> Result<Vec<B256>> {
+        let block_hash = self.get_block_hash(block_number).await?;
 
         let grandpa_authorities = self
             .client
             .runtime_api()
             .at(H256::from(block_hash.0))
             .call_raw::<Vec<(ed25519::Public, u64)>>("GrandpaApi_grandpa_authorities", None)
-            .await
-            .unwrap();
+            .await?;
 
         let mut authorities: Vec<B256> = Vec::new();
         for (pub_key, weight) in grandpa_authorities {
             authorities.push(B256::from(pub_key.0));
             let expected_weight = 1;
-            // Assert the LE representation of the weight of each validator is 1.
-            assert_eq!(
-                weight, expected_weight,
-                "The weight of the authority is not 1!"
-            );
+            // The LE representation of the weight of each validator must be 1.
+            if weight != expected_weight {
+                return Err(anyhow::anyhow!(
+                    "The weight of authority {:?} at block {} is {}, not 1",
+                    pub_key,
+                    block_number,
+                    weight
+                ));
+            }
         }
 
-        authorities
+        Ok(authorities)
     }
 
     /// Gets the authority set id and authority set hash that are defined in block_number. This authority set
     /// attests to block_number + 1.
-    pub async fn get_authority_set_data_for_block(&self, block_number: u32) -> (u64, B256) {
-        let authority_set_id = self.get_authority_set_id(block_number).await;
+    pub async fn get_authority_set_data_for_block(&self, block_number: u32) -> Result<(u64, B256)> {
+        let authority_set_id = self.get_authority_set_id(block_number).await?;
         let authority_set_hash = self
             .compute_authority_set_hash_for_block(block_number)
-            .await;
-        (authority_set_id, authority_set_hash)
+            .await?;
+        Ok((authority_set_id, authority_set_hash))
     }
 
     /// Computes the authority_set_hash for a given block number. Note: This is the authority set hash
     /// that validates the next block after the given block number.
-    pub async fn compute_authority_set_hash_for_block(&self, block_number: u32) -> B256 {
-        let authorities = self.get_authorities(block_number).await;
-        compute_authority_set_commitment(authorities.as_slice())
+    pub async fn compute_authority_set_hash_for_block(&self, block_number: u32) -> Result<B256> {
+        let authorities = self.get_authorities(block_number).await?;
+        Ok(compute_authority_set_commitment(authorities.as_slice()))
     }
 
     /// Get the justification data necessary for the circuit using GrandpaJustification and the block number.
@@ -322,14 +660,47 @@ impl RpcDataFetcher {
         &self,
         justification: GrandpaJustification,
         block_number: u32,
-    ) -> CircuitJustification {
+    ) -> Result<CircuitJustification> {
         // Get the authority set id that attested to block_number.
-        let authority_set_id = self.get_authority_set_id(block_number - 1).await;
+        let authority_set_id = self.get_authority_set_id(block_number - 1).await?;
 
         // Get the authority set for the block number.
-        let authorities = self.get_authorities(block_number - 1).await;
+        let authorities = self.get_authorities(block_number - 1).await?;
 
-        convert_justification_and_valset_to_circuit(justification, authorities, authority_set_id)
+        Ok(convert_justification_and_valset_to_circuit(
+            justification,
+            authorities,
+            authority_set_id,
+        ))
+    }
+
+    /// Verify a justification against the authority set that attested to its target block, and check
+    /// that the target block is on the canonical chain. Returns the header of the target block.
+    pub async fn verify_grandpa_justification(
+        &self,
+        justification: &GrandpaJustification,
+    ) -> Result<Header> {
+        let block_number = justification.commit.target_number;
+        if block_number == 0 {
+            return Err(anyhow::anyhow!("Justification for the genesis block"));
+        }
+
+        let block_hash = self.get_block_hash(block_number).await?;
+        if block_hash.0 != justification.commit.target_hash.0 {
+            return Err(anyhow::anyhow!(
+                "Justification is for block hash {:?}, but block {} has hash {}",
+                justification.commit.target_hash,
+                block_number,
+                block_hash
+            ));
+        }
+
+        let circuit_justification = self
+            .compute_data_from_justification(justification.clone(), block_number)
+            .await?;
+        check_justification(&circuit_justification)?;
+
+        self.get_header_by_hash(block_hash).await
     }
 
     /// Get the justification for a block using the DB cache from the justification indexer.
@@ -343,7 +714,21 @@ impl RpcDataFetcher {
                 self.get_justification_data_for_block_unsafe(block_number)
                     .await
             }
-            false => self.get_justification(block_number).await,
+            false => match self.get_justification(block_number).await {
+                Ok(justification) => Ok(justification),
+                // The indexer may have missed this block. Fall back to the justification stored by
+                // the Avail node, which is only usable if it is for exactly this block.
+                Err(e) => self
+                    .get_justification_data_for_block_unsafe(block_number)
+                    .await
+                    .and_then(|justification| {
+                        if justification.commit.target_number == block_number {
+                            Ok(justification)
+                        } else {
+                            Err(e)
+                        }
+                    }),
+            },
         };
 
         if grandpa_justification.is_err() {
@@ -352,16 +737,26 @@ impl RpcDataFetcher {
         let grandpa_justification = grandpa_justification.unwrap();
 
         // Convert DB stored justification into CircuitJustification.
-        let circuit_justification = self
+        match self
             .compute_data_from_justification(grandpa_justification, block_number)
-            .await;
-        Some(circuit_justification)
+            .await
+        {
+            Ok(circuit_justification) => Some(circuit_justification),
+            Err(e) => {
+                tracing::debug!(
+                    "Failed to get the authority set of the justification for block {}: {:?}",
+                    block_number,
+                    e
+                );
+                None
+            }
+        }
     }
 
     /// Get the latest justification data. Because Avail does not store the justification data for
     /// all blocks, we can only generate a proof using the latest justification data or the justification data for a specific block.
-    pub async fn get_latest_justification_data(&self) -> (CircuitJustification, Header) {
-        let sub: Result<RpcSubscription<GrandpaJustification>, _> = self
+    pub async fn get_latest_justification_data(&self) -> Result<(CircuitJustification, Header)> {
+        let mut sub: RpcSubscription<GrandpaJustification> = self
             .client
             .rpc()
             .subscribe(
@@ -369,8 +764,7 @@ impl RpcDataFetcher {
                 RpcParams::new(),
                 "grandpa_unsubscribeJustifications",
             )
-            .await;
-        let mut sub = sub.unwrap();
+            .await?;
 
         // Wait for new justification.
         if let Some(Ok(justification)) = sub.next().await {
@@ -379,20 +773,27 @@ impl RpcDataFetcher {
                 .client
                 .legacy_rpc()
                 .chain_get_header(Some(justification.commit.target_hash))
-                .await
-                .unwrap()
-                .unwrap();
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Header {:?} of the latest justification not found",
+                        justification.commit.target_hash
+                    )
+                })?;
             let block_number = header.number;
-            return (
+            return Ok((
                 self.compute_data_from_justification(justification, block_number)
-                    .await,
+                    .await?,
                 header,
-            );
+            ));
         }
-        panic!("No justification found")
+        Err(anyhow::anyhow!("No justification found"))
     }
 
     /// Get the justification data for a block number. Unsafe, not guaranteed to be correct.
+    ///
+    /// Note: The Avail node only stores justifications for some blocks (e.g. epoch end blocks), so the
+    /// returned justification is for the first block >= the requested block that has one.
     pub async fn get_justification_data_for_block_unsafe(
         &self,
         epoch_end_block: u32,
@@ -404,18 +805,91 @@ impl RpcDataFetcher {
         let encoded_finality_proof = self
             .client
             .rpc()
-            .request::<EncodedFinalityProof>("grandpa_proveFinality", params)
-            .await
-            .unwrap();
+            .request::<Option<EncodedFinalityProof>>("grandpa_proveFinality", params)
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!("No finality proof available for block {}", epoch_end_block)
+            })?;
 
         let finality_proof: FinalityProof =
-            Decode::decode(&mut encoded_finality_proof.0 .0.as_slice()).unwrap();
+            Decode::decode(&mut encoded_finality_proof.0 .0.as_slice())?;
         let justification: GrandpaJustification =
-            Decode::decode(&mut finality_proof.justification.as_slice()).unwrap();
+            Decode::decode(&mut finality_proof.justification.as_slice())?;
 
         Ok(justification)
     }
 
+    /// Find the first block in [start_block, end_block] inclusive with an available justification,
+    /// recording the ranges of blocks that are missing from the justification indexer.
+    ///
+    /// When reading from the database, all of the justified blocks in the range are fetched in a single
+    /// query. Otherwise, each block is queried in turn. If the indexer has no justification in the
+    /// range, fall back to the justifications stored by the Avail node via `grandpa_proveFinality`.
+    pub async fn find_justified_block(
+        &self,
+        start_block: u32,
+        end_block: u32,
+    ) -> JustificationDiscovery {
+        let mut discovery = JustificationDiscovery::default();
+        if start_block > end_block {
+            return discovery;
+        }
+
+        let justified_blocks = match self.justification_store.as_ref() {
+            Some(justification_store) => justification_store
+                .get_justified_block_numbers(&self.avail_chain_id, start_block, end_block)
+                .await
+                .map_err(|e| {
+                    tracing::warn!(
+                        "Failed to query justified blocks from the database: {:?}",
+                        e
+                    );
+                })
+                .ok(),
+            None => None,
+        };
+
+        if let Some(justified_blocks) = justified_blocks {
+            discovery.gaps = find_gaps(start_block, end_block, &justified_blocks);
+            discovery.justified_block = justified_blocks.first().copied();
+        } else {
+            for block_number in start_block..=end_block {
+                if self.get_justification(block_number).await.is_ok() {
+                    discovery.justified_block = Some(block_number);
+                    break;
+                }
+            }
+            let last_searched_block = discovery
+                .justified_block
+                .map_or(end_block, |block_number| block_number - 1);
+            if last_searched_block >= start_block {
+                discovery.gaps.push((start_block, last_searched_block));
+            }
+        }
+
+        if discovery.justified_block.is_none() {
+            match self.get_justification_data_for_block_unsafe(start_block).await {
+                Ok(justification) if justification.commit.target_number <= end_block => {
+                    discovery.justified_block = Some(justification.commit.target_number);
+                    discovery.from_finality_proof = true;
+                }
+                Ok(justification) => tracing::debug!(
+                    "First finality proof after block {} is for block {}, outside of the range ending at {}",
+                    start_block,
+                    justification.commit.target_number,
+                    end_block
+                ),
+                Err(e) => tracing::debug!(
+                    "Failed to get finality proof for block {}: {:?}",
+                    start_block,
+                    e
+                ),
+            }
+        }
+
+        discovery
+    }
+
     /// Get the justification data for an epoch end block from the curr_authority_set_id to the next authority set id.
     /// Fetch the authority set and justification proof for the last block in the current epoch. If the finality proof is a
     /// simple justification, return a CircuitJustification with the encoded precommit that all
@@ -423,16 +897,20 @@ impl RpcDataFetcher {
     pub async fn get_justification_data_epoch_end_block(
         &self,
         curr_authority_set_id: u64,
-    ) -> CircuitJustification {
-        let epoch_end_block = self.last_justified_block(curr_authority_set_id).await;
-        if epoch_end_block == 0 {
-            panic!("Current authority set is still active!");
-        }
+    ) -> Result<CircuitJustification> {
+        let epoch_end_block = self.epoch_end_block(curr_authority_set_id).await?;
 
         let grandpa_justification = self
             .get_justification_data_for_block_unsafe(epoch_end_block)
             .await
-            .expect("No justification found");
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "No justification found for epoch end block {} of authority set {}: {}",
+                    epoch_end_block,
+                    curr_authority_set_id,
+                    e
+                )
+            })?;
         self.compute_data_from_justification(grandpa_justification, epoch_end_block)
             .await
     }
@@ -443,13 +921,10 @@ impl RpcDataFetcher {
     pub async fn filter_auth_set_changes(
         &self,
         authority_set_id: u64,
-    ) -> Vec<Vec<(AuthorityId, u64)>> {
-        let epoch_end_block = self.last_justified_block(authority_set_id).await;
-        if epoch_end_block == 0 {
-            panic!("Current authority set is still active!");
-        }
+    ) -> Result<Vec<Vec<(AuthorityId, u64)>>> {
+        let epoch_end_block = self.epoch_end_block(authority_set_id).await?;
 
-        let header = self.get_header(epoch_end_block).await;
+        let header = self.get_header(epoch_end_block).await?;
 
         let new_auths = header
             .digest
@@ -467,25 +942,22 @@ impl RpcDataFetcher {
                 _ => None,
             })
             .collect::<Vec<_>>();
-        new_auths
+        Ok(new_auths)
     }
 
     /// This function takes in a block_number as input, and fetches the new authority set specified
     /// in the epoch end block. It returns the data necessary to prove the new authority set, which
     /// specifies the new authority set hash, the number of authorities, and the start and end
     /// position of the encoded new authority set in the header.
-    pub async fn get_header_rotate(&self, authority_set_id: u64) -> HeaderRotateData {
-        let epoch_end_block = self.last_justified_block(authority_set_id).await;
-        if epoch_end_block == 0 {
-            panic!("Current authority set is still active!");
-        }
+    pub async fn get_header_rotate(&self, authority_set_id: u64) -> Result<HeaderRotateData> {
+        let epoch_end_block = self.epoch_end_block(authority_set_id).await?;
 
-        let header = self.get_header(epoch_end_block).await;
+        let header = self.get_header(epoch_end_block).await?;
 
         let header_bytes = header.encode();
 
         // Fetch the new authority set specified in the epoch end block.
-        let expected_new_authorities = self.get_authorities(epoch_end_block).await;
+        let expected_new_authorities = self.get_authorities(epoch_end_block).await?;
 
         let mut position = 0;
         let number_encoded = Compact(epoch_end_block).encode();
@@ -521,23 +993,26 @@ impl RpcDataFetcher {
             }
         }
 
-        // Panic if there is not a consensus log.
         if !found_correct_log {
-            panic!(
-                "Block: {:?} should be an epoch end block, but did not find corresponding consensus log!",
+            return Err(anyhow::anyhow!(
+                "Block {} should be an epoch end block, but did not find corresponding consensus log",
                 epoch_end_block
-            );
+            ));
         }
 
         let extracted_new_authorities =
             get_next_validator_pubkeys_from_epoch_end_header(&header_bytes, position);
+        if extracted_new_authorities != expected_new_authorities {
+            return Err(anyhow::anyhow!(
+                "The new authorities in the header of epoch end block {} do not match the authorities stored at the block",
+                epoch_end_block
+            ));
+        }
 
-        assert_eq!(extracted_new_authorities, expected_new_authorities);
-
-        HeaderRotateData {
+        Ok(HeaderRotateData {
             header_bytes,
             consensus_log_position: position,
-        }
+        })
     }
 }
 
@@ -575,6 +1050,23 @@ pub fn convert_justification_and_valset_to_circuit(
     }
 }
 
+/// Get the ranges of blocks, inclusive, in [start_block, end_block] that are not in the sorted list of
+/// justified blocks.
+fn find_gaps(start_block: u32, end_block: u32, justified_blocks: &[u32]) -> Vec<(u32, u32)> {
+    let mut gaps = Vec::new();
+    let mut next_expected_block = start_block;
+    for &block_number in justified_blocks {
+        if block_number > next_expected_block {
+            gaps.push((next_expected_block, block_number - 1));
+        }
+        next_expected_block = block_number + 1;
+    }
+    if next_expected_block <= end_block {
+        gaps.push((next_expected_block, end_block));
+    }
+    gaps
+}
+
 /// NOTE: ONLY USED IN TESTING. IN PROD, FETCH FROM CONTRACT.
 fn get_merkle_tree_size(num_headers: u32) -> usize {
     let mut size = 1;
@@ -599,6 +1091,27 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_query_response_omits_unset_fields() {
+        let response = VectorXQueryResponse {
+            success: false,
+            justification: None,
+            error: Some("No justification found".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "success": false, "error": "No justification found" })
+        );
+
+        let response: VectorXQueryResponse =
+            serde_json::from_value(serde_json::json!({ "success": false })).unwrap();
+        assert!(response.justification.is_none() && response.error.is_none());
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "success": false })
+        );
+    }
+
     #[tokio::test]
     async fn test_get_simple_justification_change_authority_set() {
         let fetcher = RpcDataFetcher::new().await;
@@ -606,11 +1119,12 @@ mod tests {
         // This is an block in the middle of an era.
         let block = 645570;
 
-        let authority_set_id = fetcher.get_authority_set_id(block - 1).await;
+        let authority_set_id = fetcher.get_authority_set_id(block - 1).await.unwrap();
         let authority_set_hash = fetcher
             .compute_authority_set_hash_for_block(block - 1)
-            .await;
-        let header = fetcher.get_header(block).await;
+            .await
+            .unwrap();
+        let header = fetcher.get_header(block).await.unwrap();
         let header_hash = header.hash();
 
         println!("authority_set_id {:?}", authority_set_id);
@@ -633,7 +1147,10 @@ mod tests {
         // target_authority_set_id. This block also specifies the new authority set,
         // target_authority_set_id + 1.
         let target_authority_set_id = 2;
-        let epoch_end_block_number = fetcher.last_justified_block(target_authority_set_id).await;
+        let epoch_end_block_number = fetcher
+            .last_justified_block(target_authority_set_id)
+            .await
+            .unwrap();
 
         // Verify that this is an epoch end block.
         assert_ne!(epoch_end_block_number, 0);
@@ -641,17 +1158,27 @@ mod tests {
 
         let previous_authority_set_id = fetcher
             .get_authority_set_id(epoch_end_block_number - 1)
-            .await;
-        let new_authority_set_id = fetcher.get_authority_set_id(epoch_end_block_number).await;
+            .await
+            .unwrap();
+        let new_authority_set_id = fetcher
+            .get_authority_set_id(epoch_end_block_number)
+            .await
+            .unwrap();
 
-        let new_authorities = fetcher.get_authorities(epoch_end_block_number).await;
+        let new_authorities = fetcher
+            .get_authorities(epoch_end_block_number)
+            .await
+            .unwrap();
         let expected_new_authority_set_hash = compute_authority_set_commitment(&new_authorities);
 
         // Verify this is an epoch end block.
         assert_eq!(previous_authority_set_id + 1, new_authority_set_id);
         assert_eq!(previous_authority_set_id, target_authority_set_id);
 
-        let rotate_data = fetcher.get_header_rotate(new_authority_set_id).await;
+        let rotate_data = fetcher
+            .get_header_rotate(new_authority_set_id)
+            .await
+            .unwrap();
         let new_authority_set_hash =
             compute_authority_set_commitment(&get_next_validator_pubkeys_from_epoch_end_header(
                 &rotate_data.header_bytes,
@@ -679,6 +1206,17 @@ mod tests {
         assert_eq!(msg1, msg2, "Messages are not equal")
     }
 
+    #[test]
+    fn test_find_gaps() {
+        assert_eq!(find_gaps(10, 20, &[]), vec![(10, 20)]);
+        assert_eq!(find_gaps(10, 12, &[10, 11, 12]), vec![]);
+        assert_eq!(
+            find_gaps(10, 20, &[12, 13, 17]),
+            vec![(10, 11), (14, 16), (18, 20)]
+        );
+        assert_eq!(find_gaps(10, 20, &[10, 20]), vec![(11, 19)]);
+    }
+
     #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct JsonGrandpaJustification {
         pub round: u64,
@@ -1,6 +1,14 @@
+pub mod authority_set;
+pub mod backfill;
+pub mod contract;
+pub mod data_root;
 pub mod input;
 pub mod postgres;
+pub mod retention;
+pub mod sqlite;
+pub mod store;
 pub mod types;
+pub mod verify;
 
 pub use timeout::Timeout;
 
@@ -1,28 +1,44 @@
+use alloy::primitives::B256;
 use anyhow::Result;
-use serde_json::{from_str, to_value};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use serde_json::to_value;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
 use sqlx::{PgPool, Row};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::types::GrandpaJustification;
+use crate::store::{
+    decode_authorities, encode_authorities, JustificationStore, LegacyConversionBatch, PruneMode,
+};
+use crate::types::{AuthoritySet, GrandpaJustification};
 
 pub struct PostgresClient {
     pool: PgPool,
 }
 
 impl PostgresClient {
+    /// Connect to the database at `DATABASE_URL`.
     pub async fn new() -> Result<Self> {
         let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
-        let pool = PgPool::connect(&database_url).await?;
+        Self::connect(&database_url).await
+    }
+
+    /// Connect to the database at the given URL.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = PgPool::connect(database_url).await?;
 
         // Test the connection
         sqlx::query("SELECT 1").execute(&pool).await?;
 
         Ok(PostgresClient { pool })
     }
+}
 
-    /// Add a justification to the PostgreSQL table.
-    pub async fn add_justification(
+#[async_trait]
+impl JustificationStore for PostgresClient {
+    async fn add_justification(
         &self,
         avail_chain_id: &str,
         justification: GrandpaJustification,
@@ -37,29 +53,58 @@ impl PostgresClient {
         );
 
         sqlx::query(
-            "INSERT INTO justifications (id, avail_chain_id, block_number, data) 
-             VALUES ($1, $2, $3, $4) 
+            "INSERT INTO justifications (id, avail_chain_id, block_number, data, encoded) 
+             VALUES ($1, $2, $3, $4, $5) 
              ON CONFLICT (avail_chain_id, block_number) 
-             DO UPDATE SET data = $4, created_at = NOW()",
+             DO NOTHING",
         )
         .bind(&id)
         .bind(avail_chain_id)
         .bind(block_nb as i32)
         .bind(&json_data)
+        .bind(justification.encode())
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    /// Get a justification from the PostgreSQL table.
-    pub async fn get_justification(
+    async fn add_rejected_justification(
+        &self,
+        avail_chain_id: &str,
+        justification: &GrandpaJustification,
+        reason: &str,
+    ) -> Result<()> {
+        let json_data = to_value(justification)?;
+        let block_nb = justification.commit.target_number;
+
+        info!(
+            "Recording rejected justification for chain: {} for block number: {:?}",
+            avail_chain_id, block_nb
+        );
+
+        sqlx::query(
+            "INSERT INTO rejected_justifications (avail_chain_id, block_number, reason, data)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(avail_chain_id)
+        .bind(block_nb as i32)
+        .bind(reason)
+        .bind(&json_data)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_justification(
         &self,
         avail_chain_id: &str,
         block_number: u32,
     ) -> Result<GrandpaJustification> {
+        // Only read the JSON form for legacy rows without the SCALE encoding.
         let row = sqlx::query(
-            "SELECT data FROM justifications 
+            "SELECT encoded, CASE WHEN encoded IS NULL THEN data END AS data FROM justifications 
              WHERE avail_chain_id = $1 AND block_number = $2",
         )
         .bind(avail_chain_id)
@@ -68,20 +113,17 @@ impl PostgresClient {
         .await?;
 
         if let Some(row) = row {
-            let json_data: String = row.get("data");
-            let data: GrandpaJustification = from_str(&json_data)?;
+            if let Some(encoded) = row.try_get::<Option<Vec<u8>>, _>("encoded")? {
+                return Ok(GrandpaJustification::decode(&mut encoded.as_slice())?);
+            }
+            let Json(data): Json<GrandpaJustification> = row.try_get("data")?;
             Ok(data)
         } else {
             Err(anyhow::anyhow!("Justification not found"))
         }
     }
 
-    /// Check if a justification exists for the given chain and block number.
-    pub async fn justification_exists(
-        &self,
-        avail_chain_id: &str,
-        block_number: u32,
-    ) -> Result<bool> {
+    async fn justification_exists(&self, avail_chain_id: &str, block_number: u32) -> Result<bool> {
         let row = sqlx::query(
             "SELECT 1 FROM justifications 
              WHERE avail_chain_id = $1 AND block_number = $2",
@@ -94,8 +136,101 @@ impl PostgresClient {
         Ok(row.is_some())
     }
 
-    /// Get the latest block number for a given chain.
-    pub async fn get_latest_block_number(&self, avail_chain_id: &str) -> Result<Option<u32>> {
+    async fn get_justified_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>> {
+        let rows = sqlx::query(
+            "SELECT block_number FROM justifications
+             WHERE avail_chain_id = $1 AND block_number BETWEEN $2 AND $3
+             ORDER BY block_number ASC",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i32)
+        .bind(end_block as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok(row.try_get::<i32, _>("block_number")? as u32))
+            .collect()
+    }
+
+    async fn get_missing_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>> {
+        let rows = sqlx::query(
+            "SELECT gs.block_number FROM generate_series($2::INTEGER, $3::INTEGER) AS gs(block_number)
+             LEFT JOIN justifications j
+             ON j.avail_chain_id = $1 AND j.block_number = gs.block_number
+             WHERE j.block_number IS NULL
+             ORDER BY gs.block_number ASC",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i32)
+        .bind(end_block as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok(row.try_get::<i32, _>("block_number")? as u32))
+            .collect()
+    }
+
+    async fn add_unjustified_range(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO unjustified_ranges (avail_chain_id, start_block, end_block)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (avail_chain_id, start_block)
+             DO UPDATE SET end_block = GREATEST(unjustified_ranges.end_block, EXCLUDED.end_block)",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i32)
+        .bind(end_block as i32)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_unjustified_ranges(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<(u32, u32)>> {
+        let rows = sqlx::query(
+            "SELECT start_block, end_block FROM unjustified_ranges
+             WHERE avail_chain_id = $1 AND start_block <= $3 AND end_block >= $2
+             ORDER BY start_block ASC",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i32)
+        .bind(end_block as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<i32, _>("start_block")? as u32,
+                    row.try_get::<i32, _>("end_block")? as u32,
+                ))
+            })
+            .collect()
+    }
+
+    async fn get_latest_block_number(&self, avail_chain_id: &str) -> Result<Option<u32>> {
         let row = sqlx::query(
             "SELECT MAX(block_number) as latest_block 
              FROM justifications 
@@ -112,6 +247,184 @@ impl PostgresClient {
             Ok(None)
         }
     }
+
+    async fn convert_legacy_justifications(
+        &self,
+        after_id: &str,
+        batch_size: usize,
+    ) -> Result<LegacyConversionBatch> {
+        let rows = sqlx::query(
+            "SELECT id, data FROM justifications
+             WHERE encoded IS NULL AND id > $1
+             ORDER BY id ASC
+             LIMIT $2",
+        )
+        .bind(after_id)
+        .bind(batch_size as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut batch = LegacyConversionBatch::default();
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            match row.try_get::<Json<GrandpaJustification>, _>("data") {
+                Ok(Json(justification)) => {
+                    sqlx::query("UPDATE justifications SET encoded = $1 WHERE id = $2")
+                        .bind(justification.encode())
+                        .bind(&id)
+                        .execute(&self.pool)
+                        .await?;
+                    batch.converted += 1;
+                }
+                Err(e) => warn!("Failed to decode legacy justification {}: {:?}", id, e),
+            }
+            batch.last_id = Some(id);
+        }
+
+        Ok(batch)
+    }
+
+    async fn prune_justifications(
+        &self,
+        avail_chain_id: &str,
+        prune_before: u32,
+        keep_blocks: &[u32],
+        mode: PruneMode,
+    ) -> Result<u64> {
+        let keep_blocks: Vec<i32> = keep_blocks.iter().map(|&b| b as i32).collect();
+
+        let query = match mode {
+            PruneMode::Delete => {
+                "DELETE FROM justifications
+                 WHERE avail_chain_id = $1 AND block_number < $2 AND NOT (block_number = ANY($3))"
+            }
+            PruneMode::Archive => {
+                "WITH pruned AS (
+                     DELETE FROM justifications
+                     WHERE avail_chain_id = $1 AND block_number < $2 AND NOT (block_number = ANY($3))
+                     RETURNING id, avail_chain_id, block_number, data, encoded, created_at
+                 )
+                 INSERT INTO justifications_archive (id, avail_chain_id, block_number, data, encoded, created_at)
+                 SELECT id, avail_chain_id, block_number, data, encoded, created_at FROM pruned
+                 ON CONFLICT (id)
+                 DO UPDATE SET data = EXCLUDED.data, encoded = EXCLUDED.encoded, archived_at = NOW()"
+            }
+        };
+
+        let result = sqlx::query(query)
+            .bind(avail_chain_id)
+            // Bound as a BIGINT, so a bound above i32::MAX does not wrap to a negative block.
+            .bind(prune_before as i64)
+            .bind(&keep_blocks)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn add_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set: &AuthoritySet,
+    ) -> Result<()> {
+        info!(
+            "Adding authority set {} for chain: {}",
+            authority_set.authority_set_id, avail_chain_id
+        );
+
+        sqlx::query(
+            "INSERT INTO authority_sets (avail_chain_id, authority_set_id, authorities, authority_set_hash, start_block, epoch_end_block, change_type)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (avail_chain_id, authority_set_id)
+             DO UPDATE SET authorities = EXCLUDED.authorities,
+                 authority_set_hash = EXCLUDED.authority_set_hash,
+                 start_block = COALESCE(EXCLUDED.start_block, authority_sets.start_block),
+                 epoch_end_block = COALESCE(EXCLUDED.epoch_end_block, authority_sets.epoch_end_block),
+                 change_type = COALESCE(EXCLUDED.change_type, authority_sets.change_type)",
+        )
+        .bind(avail_chain_id)
+        .bind(authority_set.authority_set_id as i64)
+        .bind(encode_authorities(&authority_set.authorities))
+        .bind(authority_set.authority_set_hash.as_slice())
+        .bind(authority_set.start_block.map(|b| b as i32))
+        .bind(authority_set.epoch_end_block.map(|b| b as i32))
+        .bind(authority_set.change_type.map(|c| c.as_str()))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set_id: u64,
+    ) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = $1 AND authority_set_id = $2",
+        )
+        .bind(avail_chain_id)
+        .bind(authority_set_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+
+    async fn get_latest_authority_set(&self, avail_chain_id: &str) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = $1
+             ORDER BY authority_set_id DESC
+             LIMIT 1",
+        )
+        .bind(avail_chain_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+
+    async fn get_authority_set_for_block(
+        &self,
+        avail_chain_id: &str,
+        block_number: u32,
+    ) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = $1
+             AND start_block < $2
+             AND epoch_end_block >= $2
+             ORDER BY authority_set_id DESC
+             LIMIT 1",
+        )
+        .bind(avail_chain_id)
+        .bind(block_number as i32)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+}
+
+fn authority_set_from_row(row: &PgRow) -> Result<AuthoritySet> {
+    let authorities: Vec<u8> = row.try_get("authorities")?;
+    let authority_set_hash: Vec<u8> = row.try_get("authority_set_hash")?;
+    let change_type: Option<String> = row.try_get("change_type")?;
+
+    Ok(AuthoritySet {
+        authority_set_id: row.try_get::<i64, _>("authority_set_id")? as u64,
+        authorities: decode_authorities(&authorities)?,
+        authority_set_hash: B256::try_from(authority_set_hash.as_slice())?,
+        start_block: row
+            .try_get::<Option<i32>, _>("start_block")?
+            .map(|b| b as u32),
+        epoch_end_block: row
+            .try_get::<Option<i32>, _>("epoch_end_block")?
+            .map(|b| b as u32),
+        change_type: change_type.map(|c| c.parse()).transpose()?,
+    })
 }
 
 #[cfg(test)]
@@ -189,4 +502,85 @@ mod tests {
             .expect("Failed to get latest block number");
         assert_eq!(latest, Some(block_number));
     }
+
+    #[tokio::test]
+    #[ignore] // This test requires a PostgreSQL database
+    async fn test_postgres_prune_justifications() {
+        dotenv::dotenv().ok();
+
+        // Skip test if DATABASE_URL is not set
+        if std::env::var("DATABASE_URL").is_err() {
+            println!("Skipping test: DATABASE_URL not set");
+            return;
+        }
+
+        let client = PostgresClient::new()
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-prune-chain";
+
+        // Start from an empty chain, in case a previous run left rows behind.
+        sqlx::query("DELETE FROM justifications WHERE avail_chain_id = $1")
+            .bind(chain_id)
+            .execute(&client.pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM justifications_archive WHERE avail_chain_id = $1")
+            .bind(chain_id)
+            .execute(&client.pool)
+            .await
+            .unwrap();
+
+        for block_number in 10..20 {
+            client
+                .add_justification(
+                    chain_id,
+                    GrandpaJustification {
+                        round: 1,
+                        commit: Commit {
+                            target_hash: H256::from_slice(&[1u8; 32]),
+                            target_number: block_number,
+                            precommits: vec![],
+                        },
+                        votes_ancestries: vec![],
+                    },
+                )
+                .await
+                .expect("Failed to add justification");
+        }
+
+        let removed = client
+            .prune_justifications(chain_id, 15, &[11, 13], PruneMode::Archive)
+            .await
+            .unwrap();
+        assert_eq!(removed, 3);
+        assert_eq!(
+            client
+                .get_justified_block_numbers(chain_id, 0, 100)
+                .await
+                .unwrap(),
+            vec![11, 13, 15, 16, 17, 18, 19]
+        );
+        let archived: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM justifications_archive WHERE avail_chain_id = $1",
+        )
+        .bind(chain_id)
+        .fetch_one(&client.pool)
+        .await
+        .unwrap();
+        assert_eq!(archived, 3);
+
+        let removed = client
+            .prune_justifications(chain_id, 17, &[], PruneMode::Delete)
+            .await
+            .unwrap();
+        assert_eq!(removed, 4);
+        assert_eq!(
+            client
+                .get_justified_block_numbers(chain_id, 0, 100)
+                .await
+                .unwrap(),
+            vec![17, 18, 19]
+        );
+    }
 }
@@ -0,0 +1,246 @@
+//! Retention policy for the justification indexer. The operator only needs justifications near the
+//! head and for epoch end blocks, so older justifications are pruned on a schedule.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tracing::{error, info};
+
+use crate::input::RpcDataFetcher;
+use crate::store::{JustificationStore, PruneMode};
+
+/// The default interval between pruning runs.
+const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The maximum number of epoch end blocks looked up over RPC per pruning run, for the authority
+/// sets missing from the store. Pruning stops below the first authority set that is still unknown,
+/// and catches up over the following runs.
+const MAX_EPOCH_END_RPC_LOOKUPS: usize = 64;
+
+/// Which justifications to keep, and how often to prune the rest.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// Keep every justification within this many blocks of the latest stored block.
+    pub keep_recent_blocks: u32,
+    /// Blocks to always keep, in addition to the epoch end blocks.
+    pub pinned_blocks: Vec<u32>,
+    pub mode: PruneMode,
+    pub interval: Duration,
+}
+
+impl RetentionPolicy {
+    /// Read the policy from the `RETENTION_KEEP_BLOCKS`, `RETENTION_PINNED_BLOCKS` (comma separated),
+    /// `RETENTION_MODE` (archive or delete) and `RETENTION_INTERVAL_MINS` environment variables.
+    /// Pruning is disabled if `RETENTION_KEEP_BLOCKS` is not set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(keep_recent_blocks) = env::var("RETENTION_KEEP_BLOCKS") else {
+            return Ok(None);
+        };
+
+        let pinned_blocks = match env::var("RETENTION_PINNED_BLOCKS") {
+            Ok(pinned_blocks) => pinned_blocks
+                .split(',')
+                .map(str::trim)
+                .filter(|block| !block.is_empty())
+                .map(|block| block.parse())
+                .collect::<Result<Vec<u32>, _>>()?,
+            Err(_) => Vec::new(),
+        };
+        let mode = match env::var("RETENTION_MODE") {
+            Ok(mode) => mode.parse()?,
+            Err(_) => PruneMode::default(),
+        };
+        let interval = match env::var("RETENTION_INTERVAL_MINS") {
+            Ok(mins) => Duration::from_secs(mins.parse::<u64>()? * 60),
+            Err(_) => DEFAULT_PRUNE_INTERVAL,
+        };
+
+        Ok(Some(Self {
+            keep_recent_blocks: keep_recent_blocks.parse()?,
+            pinned_blocks,
+            mode,
+            interval,
+        }))
+    }
+}
+
+/// Summary of a pruning run.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PruneStats {
+    /// Justifications below this block were pruned, except for the kept blocks.
+    pub prune_before: u32,
+    /// The number of epoch end and pinned blocks below `prune_before` that were kept.
+    pub kept_blocks: usize,
+    /// The number of rows deleted or archived.
+    pub rows_removed: u64,
+    pub duration: Duration,
+}
+
+/// Prunes the justifications of one chain according to a [`RetentionPolicy`].
+pub struct Pruner {
+    fetcher: Arc<RpcDataFetcher>,
+    store: Arc<dyn JustificationStore>,
+    policy: RetentionPolicy,
+    /// The epoch end block of each authority set id. These never change, so they are only looked up
+    /// once.
+    epoch_end_blocks: BTreeMap<u64, u32>,
+    /// The number of rows removed, shared with the caller through [`Pruner::rows_removed`].
+    rows_removed: Arc<AtomicU64>,
+}
+
+impl Pruner {
+    pub fn new(
+        fetcher: Arc<RpcDataFetcher>,
+        store: Arc<dyn JustificationStore>,
+        policy: RetentionPolicy,
+    ) -> Self {
+        Self {
+            fetcher,
+            store,
+            policy,
+            epoch_end_blocks: BTreeMap::new(),
+            rows_removed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Count the removed rows in the given counter, e.g. to keep the total across restarts of the
+    /// pruner.
+    pub fn with_rows_removed(mut self, rows_removed: Arc<AtomicU64>) -> Self {
+        self.rows_removed = rows_removed;
+        self
+    }
+
+    /// The number of rows removed by this pruner, and any other sharing its counter.
+    pub fn rows_removed(&self) -> Arc<AtomicU64> {
+        self.rows_removed.clone()
+    }
+
+    /// Look up the epoch end blocks of the authority sets before `authority_set_id`, from the store
+    /// and then over RPC, with at most [`MAX_EPOCH_END_RPC_LOOKUPS`] RPC lookups. Returns the id of
+    /// the first authority set whose epoch end block is still unknown, if any.
+    async fn resolve_epoch_end_blocks(&mut self, authority_set_id: u64) -> Result<Option<u64>> {
+        let avail_chain_id = &self.fetcher.avail_chain_id;
+        let mut rpc_lookups = 0;
+        for set_id in 0..authority_set_id {
+            if self.epoch_end_blocks.contains_key(&set_id) {
+                continue;
+            }
+
+            let stored = self
+                .store
+                .get_authority_set(avail_chain_id, set_id)
+                .await?
+                .and_then(|authority_set| authority_set.epoch_end_block);
+            let epoch_end_block = match stored {
+                Some(epoch_end_block) => epoch_end_block,
+                None if rpc_lookups < MAX_EPOCH_END_RPC_LOOKUPS => {
+                    rpc_lookups += 1;
+                    self.fetcher
+                        .last_justified_block(set_id)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Failed to find the epoch end block of authority set {}",
+                                set_id
+                            )
+                        })?
+                }
+                None => return Ok(Some(set_id)),
+            };
+            self.epoch_end_blocks.insert(set_id, epoch_end_block);
+        }
+
+        Ok(None)
+    }
+
+    /// Prune the justifications more than `keep_recent_blocks` behind the latest stored block, except
+    /// for the epoch end and pinned blocks.
+    pub async fn prune(&mut self) -> Result<PruneStats> {
+        let start_time = Instant::now();
+        let fetcher = self.fetcher.clone();
+        let avail_chain_id = &fetcher.avail_chain_id;
+
+        let Some(latest_block) = self.store.get_latest_block_number(avail_chain_id).await? else {
+            return Ok(PruneStats::default());
+        };
+        let mut prune_before = latest_block.saturating_sub(self.policy.keep_recent_blocks);
+        if prune_before == 0 {
+            return Ok(PruneStats::default());
+        }
+
+        // Every authority set before the one active at prune_before ended below prune_before.
+        let authority_set_id = self
+            .fetcher
+            .get_authority_set_id(prune_before)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to get the authority set id at block {}, which the node may have pruned",
+                    prune_before
+                )
+            })?;
+        if let Some(unknown_set_id) = self.resolve_epoch_end_blocks(authority_set_id).await? {
+            // Only prune the blocks of the authority sets whose epoch end blocks are known.
+            prune_before = match unknown_set_id.checked_sub(1) {
+                Some(set_id) => prune_before.min(self.epoch_end_blocks[&set_id] + 1),
+                None => return Ok(PruneStats::default()),
+            };
+            info!(
+                "Epoch end block of authority set {} for chain {} is not known yet, pruning below block {}",
+                unknown_set_id, avail_chain_id, prune_before
+            );
+        }
+
+        let mut keep_blocks: Vec<u32> = self
+            .epoch_end_blocks
+            .values()
+            .chain(self.policy.pinned_blocks.iter())
+            .copied()
+            .filter(|&block| block < prune_before)
+            .collect();
+        keep_blocks.sort_unstable();
+        keep_blocks.dedup();
+
+        let rows_removed = self
+            .store
+            .prune_justifications(avail_chain_id, prune_before, &keep_blocks, self.policy.mode)
+            .await?;
+        self.rows_removed.fetch_add(rows_removed, Ordering::Relaxed);
+
+        Ok(PruneStats {
+            prune_before,
+            kept_blocks: keep_blocks.len(),
+            rows_removed,
+            duration: start_time.elapsed(),
+        })
+    }
+
+    /// Prune on the policy's interval, forever.
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.policy.interval);
+        loop {
+            interval.tick().await;
+
+            match self.prune().await {
+                Ok(stats) => info!(
+                    "Pruned justifications for chain {} below block {} ({:?}) in {:?}: {} rows removed, {} epoch end and pinned blocks kept, {} rows removed in total",
+                    self.fetcher.avail_chain_id,
+                    stats.prune_before,
+                    self.policy.mode,
+                    stats.duration,
+                    stats.rows_removed,
+                    stats.kept_blocks,
+                    self.rows_removed.load(Ordering::Relaxed)
+                ),
+                Err(e) => error!(
+                    "Failed to prune justifications for chain {}: {:?}",
+                    self.fetcher.avail_chain_id, e
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,730 @@
+use std::str::FromStr;
+
+use alloy::primitives::B256;
+use anyhow::Result;
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use serde_json::to_string;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqliteRow};
+use sqlx::Row;
+use tracing::{info, warn};
+
+use crate::store::{
+    decode_authorities, encode_authorities, JustificationStore, LegacyConversionBatch, PruneMode,
+};
+use crate::types::{AuthoritySet, GrandpaJustification};
+
+/// The schema of the embedded database, equivalent to the PostgreSQL migrations. Applied on every
+/// connection, so a new database file is ready to use.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS justifications (
+    id TEXT PRIMARY KEY,
+    avail_chain_id TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    encoded BLOB,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(avail_chain_id, block_number)
+);
+
+CREATE TABLE IF NOT EXISTS rejected_justifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    avail_chain_id TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    data TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_rejected_justifications_avail_chain_block ON rejected_justifications(avail_chain_id, block_number);
+
+CREATE TABLE IF NOT EXISTS justifications_archive (
+    id TEXT PRIMARY KEY,
+    avail_chain_id TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    encoded BLOB,
+    created_at TEXT,
+    archived_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS authority_sets (
+    avail_chain_id TEXT NOT NULL,
+    authority_set_id INTEGER NOT NULL,
+    authorities BLOB NOT NULL,
+    authority_set_hash BLOB NOT NULL,
+    start_block INTEGER,
+    epoch_end_block INTEGER,
+    change_type TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (avail_chain_id, authority_set_id)
+);
+
+CREATE TABLE IF NOT EXISTS unjustified_ranges (
+    avail_chain_id TEXT NOT NULL,
+    start_block INTEGER NOT NULL,
+    end_block INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (avail_chain_id, start_block)
+);
+";
+
+/// An embedded, file-based justification store, for running the indexer and operator without a
+/// PostgreSQL server.
+pub struct SqliteClient {
+    pool: SqlitePool,
+}
+
+impl SqliteClient {
+    /// Connect to the database at the given `sqlite:` URL, creating it if it does not exist.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
+
+        sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+
+        // Databases created before the SCALE-encoded column was added are missing it.
+        let encoded_columns: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('justifications') WHERE name = 'encoded'",
+        )
+        .fetch_one(&pool)
+        .await?;
+        if encoded_columns == 0 {
+            sqlx::query("ALTER TABLE justifications ADD COLUMN encoded BLOB")
+                .execute(&pool)
+                .await?;
+        }
+
+        Ok(SqliteClient { pool })
+    }
+}
+
+#[async_trait]
+impl JustificationStore for SqliteClient {
+    async fn add_justification(
+        &self,
+        avail_chain_id: &str,
+        justification: GrandpaJustification,
+    ) -> Result<()> {
+        let json_data = to_string(&justification)?;
+        let block_nb = justification.commit.target_number;
+        let id = format!("{}-{}", avail_chain_id, block_nb).to_lowercase();
+
+        info!(
+            "Adding justification for chain: {} for block number: {:?}",
+            avail_chain_id, block_nb
+        );
+
+        sqlx::query(
+            "INSERT INTO justifications (id, avail_chain_id, block_number, data, encoded)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (avail_chain_id, block_number)
+             DO NOTHING",
+        )
+        .bind(&id)
+        .bind(avail_chain_id)
+        .bind(block_nb as i64)
+        .bind(&json_data)
+        .bind(justification.encode())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn add_rejected_justification(
+        &self,
+        avail_chain_id: &str,
+        justification: &GrandpaJustification,
+        reason: &str,
+    ) -> Result<()> {
+        let json_data = to_string(justification)?;
+        let block_nb = justification.commit.target_number;
+
+        info!(
+            "Recording rejected justification for chain: {} for block number: {:?}",
+            avail_chain_id, block_nb
+        );
+
+        sqlx::query(
+            "INSERT INTO rejected_justifications (avail_chain_id, block_number, reason, data)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(avail_chain_id)
+        .bind(block_nb as i64)
+        .bind(reason)
+        .bind(&json_data)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_justification(
+        &self,
+        avail_chain_id: &str,
+        block_number: u32,
+    ) -> Result<GrandpaJustification> {
+        // Only read the JSON form for legacy rows without the SCALE encoding.
+        let row = sqlx::query(
+            "SELECT encoded, CASE WHEN encoded IS NULL THEN data END AS data FROM justifications
+             WHERE avail_chain_id = ?1 AND block_number = ?2",
+        )
+        .bind(avail_chain_id)
+        .bind(block_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            if let Some(encoded) = row.try_get::<Option<Vec<u8>>, _>("encoded")? {
+                return Ok(GrandpaJustification::decode(&mut encoded.as_slice())?);
+            }
+            let data: String = row.try_get("data")?;
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Err(anyhow::anyhow!("Justification not found"))
+        }
+    }
+
+    async fn justification_exists(&self, avail_chain_id: &str, block_number: u32) -> Result<bool> {
+        let row = sqlx::query(
+            "SELECT 1 FROM justifications
+             WHERE avail_chain_id = ?1 AND block_number = ?2",
+        )
+        .bind(avail_chain_id)
+        .bind(block_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn get_latest_block_number(&self, avail_chain_id: &str) -> Result<Option<u32>> {
+        let row = sqlx::query(
+            "SELECT MAX(block_number) as latest_block
+             FROM justifications
+             WHERE avail_chain_id = ?1",
+        )
+        .bind(avail_chain_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let latest_block: Option<i64> = row.try_get("latest_block")?;
+        Ok(latest_block.map(|b| b as u32))
+    }
+
+    async fn get_justified_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>> {
+        let rows = sqlx::query(
+            "SELECT block_number FROM justifications
+             WHERE avail_chain_id = ?1 AND block_number BETWEEN ?2 AND ?3
+             ORDER BY block_number ASC",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i64)
+        .bind(end_block as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok(row.try_get::<i64, _>("block_number")? as u32))
+            .collect()
+    }
+
+    async fn get_missing_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>> {
+        // SQLite has no generate_series, so take the complement of the justified blocks.
+        let justified_blocks = self
+            .get_justified_block_numbers(avail_chain_id, start_block, end_block)
+            .await?;
+
+        let mut justified_blocks = justified_blocks.into_iter().peekable();
+        let mut missing_blocks = Vec::new();
+        for block_number in start_block..=end_block {
+            if justified_blocks.next_if_eq(&block_number).is_none() {
+                missing_blocks.push(block_number);
+            }
+        }
+
+        Ok(missing_blocks)
+    }
+
+    async fn add_unjustified_range(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO unjustified_ranges (avail_chain_id, start_block, end_block)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (avail_chain_id, start_block)
+             DO UPDATE SET end_block = MAX(unjustified_ranges.end_block, EXCLUDED.end_block)",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i64)
+        .bind(end_block as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_unjustified_ranges(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<(u32, u32)>> {
+        let rows = sqlx::query(
+            "SELECT start_block, end_block FROM unjustified_ranges
+             WHERE avail_chain_id = ?1 AND start_block <= ?3 AND end_block >= ?2
+             ORDER BY start_block ASC",
+        )
+        .bind(avail_chain_id)
+        .bind(start_block as i64)
+        .bind(end_block as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<i64, _>("start_block")? as u32,
+                    row.try_get::<i64, _>("end_block")? as u32,
+                ))
+            })
+            .collect()
+    }
+
+    async fn convert_legacy_justifications(
+        &self,
+        after_id: &str,
+        batch_size: usize,
+    ) -> Result<LegacyConversionBatch> {
+        let rows = sqlx::query(
+            "SELECT id, data FROM justifications
+             WHERE encoded IS NULL AND id > ?1
+             ORDER BY id ASC
+             LIMIT ?2",
+        )
+        .bind(after_id)
+        .bind(batch_size as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut batch = LegacyConversionBatch::default();
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let data: String = row.try_get("data")?;
+            match serde_json::from_str::<GrandpaJustification>(&data) {
+                Ok(justification) => {
+                    sqlx::query("UPDATE justifications SET encoded = ?1 WHERE id = ?2")
+                        .bind(justification.encode())
+                        .bind(&id)
+                        .execute(&self.pool)
+                        .await?;
+                    batch.converted += 1;
+                }
+                Err(e) => warn!("Failed to decode legacy justification {}: {:?}", id, e),
+            }
+            batch.last_id = Some(id);
+        }
+
+        Ok(batch)
+    }
+
+    async fn prune_justifications(
+        &self,
+        avail_chain_id: &str,
+        prune_before: u32,
+        keep_blocks: &[u32],
+        mode: PruneMode,
+    ) -> Result<u64> {
+        // SQLite has no array parameters, so the kept blocks are passed as a JSON array.
+        let keep_blocks = to_string(keep_blocks)?;
+        let mut tx = self.pool.begin().await?;
+
+        if mode == PruneMode::Archive {
+            sqlx::query(
+                "INSERT INTO justifications_archive (id, avail_chain_id, block_number, data, encoded, created_at)
+                 SELECT id, avail_chain_id, block_number, data, encoded, created_at FROM justifications
+                 WHERE avail_chain_id = ?1 AND block_number < ?2
+                 AND block_number NOT IN (SELECT value FROM json_each(?3))
+                 ON CONFLICT (id)
+                 DO UPDATE SET data = excluded.data, encoded = excluded.encoded, archived_at = CURRENT_TIMESTAMP",
+            )
+            .bind(avail_chain_id)
+            .bind(prune_before as i64)
+            .bind(&keep_blocks)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let result = sqlx::query(
+            "DELETE FROM justifications
+             WHERE avail_chain_id = ?1 AND block_number < ?2
+             AND block_number NOT IN (SELECT value FROM json_each(?3))",
+        )
+        .bind(avail_chain_id)
+        .bind(prune_before as i64)
+        .bind(&keep_blocks)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn add_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set: &AuthoritySet,
+    ) -> Result<()> {
+        info!(
+            "Adding authority set {} for chain: {}",
+            authority_set.authority_set_id, avail_chain_id
+        );
+
+        sqlx::query(
+            "INSERT INTO authority_sets (avail_chain_id, authority_set_id, authorities, authority_set_hash, start_block, epoch_end_block, change_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (avail_chain_id, authority_set_id)
+             DO UPDATE SET authorities = EXCLUDED.authorities,
+                 authority_set_hash = EXCLUDED.authority_set_hash,
+                 start_block = COALESCE(EXCLUDED.start_block, authority_sets.start_block),
+                 epoch_end_block = COALESCE(EXCLUDED.epoch_end_block, authority_sets.epoch_end_block),
+                 change_type = COALESCE(EXCLUDED.change_type, authority_sets.change_type)",
+        )
+        .bind(avail_chain_id)
+        .bind(authority_set.authority_set_id as i64)
+        .bind(encode_authorities(&authority_set.authorities))
+        .bind(authority_set.authority_set_hash.as_slice())
+        .bind(authority_set.start_block.map(|b| b as i64))
+        .bind(authority_set.epoch_end_block.map(|b| b as i64))
+        .bind(authority_set.change_type.map(|c| c.as_str()))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set_id: u64,
+    ) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = ?1 AND authority_set_id = ?2",
+        )
+        .bind(avail_chain_id)
+        .bind(authority_set_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+
+    async fn get_latest_authority_set(&self, avail_chain_id: &str) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = ?1
+             ORDER BY authority_set_id DESC
+             LIMIT 1",
+        )
+        .bind(avail_chain_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+
+    async fn get_authority_set_for_block(
+        &self,
+        avail_chain_id: &str,
+        block_number: u32,
+    ) -> Result<Option<AuthoritySet>> {
+        let row = sqlx::query(
+            "SELECT * FROM authority_sets
+             WHERE avail_chain_id = ?1
+             AND start_block < ?2
+             AND epoch_end_block >= ?2
+             ORDER BY authority_set_id DESC
+             LIMIT 1",
+        )
+        .bind(avail_chain_id)
+        .bind(block_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| authority_set_from_row(&row)).transpose()
+    }
+}
+
+fn authority_set_from_row(row: &SqliteRow) -> Result<AuthoritySet> {
+    let authorities: Vec<u8> = row.try_get("authorities")?;
+    let authority_set_hash: Vec<u8> = row.try_get("authority_set_hash")?;
+    let change_type: Option<String> = row.try_get("change_type")?;
+
+    Ok(AuthoritySet {
+        authority_set_id: row.try_get::<i64, _>("authority_set_id")? as u64,
+        authorities: decode_authorities(&authorities)?,
+        authority_set_hash: B256::try_from(authority_set_hash.as_slice())?,
+        start_block: row
+            .try_get::<Option<i64>, _>("start_block")?
+            .map(|b| b as u32),
+        epoch_end_block: row
+            .try_get::<Option<i64>, _>("epoch_end_block")?
+            .map(|b| b as u32),
+        change_type: change_type.map(|c| c.parse()).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::convert_legacy_justifications;
+    use crate::types::{AuthoritySetChange, Commit, GrandpaJustification};
+    use sp_core::H256;
+
+    fn test_justification(block_number: u32) -> GrandpaJustification {
+        GrandpaJustification {
+            round: 1,
+            commit: Commit {
+                target_hash: H256::from_slice(&[1u8; 32]),
+                target_number: block_number,
+                precommits: vec![],
+            },
+            votes_ancestries: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_client() {
+        let client = SqliteClient::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-chain";
+
+        for block_number in [10, 12, 13] {
+            client
+                .add_justification(chain_id, test_justification(block_number))
+                .await
+                .expect("Failed to add justification");
+        }
+
+        assert!(client.justification_exists(chain_id, 12).await.unwrap());
+        assert!(!client.justification_exists(chain_id, 11).await.unwrap());
+        assert_eq!(
+            client
+                .get_justification(chain_id, 13)
+                .await
+                .unwrap()
+                .commit
+                .target_number,
+            13
+        );
+        assert_eq!(
+            client.get_latest_block_number(chain_id).await.unwrap(),
+            Some(13)
+        );
+        assert_eq!(
+            client
+                .get_justified_block_numbers(chain_id, 11, 14)
+                .await
+                .unwrap(),
+            vec![12, 13]
+        );
+        assert_eq!(
+            client
+                .get_missing_block_numbers(chain_id, 9, 14)
+                .await
+                .unwrap(),
+            vec![9, 11, 14]
+        );
+
+        // Legacy rows are only stored as JSON until they are converted.
+        sqlx::query("UPDATE justifications SET encoded = NULL WHERE block_number = 12")
+            .execute(&client.pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            client
+                .get_justification(chain_id, 12)
+                .await
+                .unwrap()
+                .commit
+                .target_number,
+            12
+        );
+        assert_eq!(convert_legacy_justifications(&client, 1).await.unwrap(), 1);
+        assert_eq!(convert_legacy_justifications(&client, 1).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_authority_sets() {
+        let client = SqliteClient::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-chain";
+
+        let authorities = vec![B256::repeat_byte(1), B256::repeat_byte(2)];
+        let mut authority_set = AuthoritySet {
+            authority_set_id: 5,
+            authorities: authorities.clone(),
+            authority_set_hash: B256::repeat_byte(3),
+            start_block: Some(100),
+            epoch_end_block: None,
+            change_type: Some(AuthoritySetChange::Scheduled),
+        };
+        client
+            .add_authority_set(chain_id, &authority_set)
+            .await
+            .unwrap();
+
+        // Recording the end of the epoch keeps the fields that are not known at the epoch end.
+        client
+            .add_authority_set(
+                chain_id,
+                &AuthoritySet {
+                    start_block: None,
+                    epoch_end_block: Some(200),
+                    change_type: None,
+                    ..authority_set.clone()
+                },
+            )
+            .await
+            .unwrap();
+        authority_set.epoch_end_block = Some(200);
+        assert_eq!(
+            client.get_authority_set(chain_id, 5).await.unwrap(),
+            Some(authority_set.clone())
+        );
+        assert_eq!(
+            client.get_latest_authority_set(chain_id).await.unwrap(),
+            Some(authority_set.clone())
+        );
+        assert_eq!(
+            client
+                .get_authority_set_for_block(chain_id, 200)
+                .await
+                .unwrap(),
+            Some(authority_set)
+        );
+        assert_eq!(
+            client
+                .get_authority_set_for_block(chain_id, 201)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_unjustified_ranges() {
+        let client = SqliteClient::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-chain";
+
+        client
+            .add_unjustified_range(chain_id, 10, 19)
+            .await
+            .unwrap();
+        client
+            .add_unjustified_range(chain_id, 30, 39)
+            .await
+            .unwrap();
+        // A longer range from the same block extends the recorded range.
+        client
+            .add_unjustified_range(chain_id, 30, 44)
+            .await
+            .unwrap();
+        client
+            .add_unjustified_range(chain_id, 30, 35)
+            .await
+            .unwrap();
+        client
+            .add_unjustified_range("other-chain", 0, 100)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client
+                .get_unjustified_ranges(chain_id, 0, 100)
+                .await
+                .unwrap(),
+            vec![(10, 19), (30, 44)]
+        );
+        assert_eq!(
+            client
+                .get_unjustified_ranges(chain_id, 19, 30)
+                .await
+                .unwrap(),
+            vec![(10, 19), (30, 44)]
+        );
+        assert_eq!(
+            client
+                .get_unjustified_ranges(chain_id, 20, 29)
+                .await
+                .unwrap(),
+            vec![]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_prune_justifications() {
+        let client = SqliteClient::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create client");
+        let chain_id = "test-chain";
+
+        for block_number in 10..20 {
+            client
+                .add_justification(chain_id, test_justification(block_number))
+                .await
+                .expect("Failed to add justification");
+        }
+
+        let removed = client
+            .prune_justifications(chain_id, 15, &[11, 13], PruneMode::Archive)
+            .await
+            .unwrap();
+        assert_eq!(removed, 3);
+        assert_eq!(
+            client
+                .get_justified_block_numbers(chain_id, 0, 100)
+                .await
+                .unwrap(),
+            vec![11, 13, 15, 16, 17, 18, 19]
+        );
+        let archived: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM justifications_archive")
+            .fetch_one(&client.pool)
+            .await
+            .unwrap();
+        assert_eq!(archived, 3);
+
+        let removed = client
+            .prune_justifications(chain_id, 17, &[], PruneMode::Delete)
+            .await
+            .unwrap();
+        assert_eq!(removed, 4);
+        assert_eq!(
+            client
+                .get_justified_block_numbers(chain_id, 0, 100)
+                .await
+                .unwrap(),
+            vec![17, 18, 19]
+        );
+    }
+}
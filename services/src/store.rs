@@ -0,0 +1,219 @@
+//! Storage backends for the justification indexer.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy::primitives::B256;
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::postgres::PostgresClient;
+use crate::sqlite::SqliteClient;
+use crate::types::{AuthoritySet, GrandpaJustification};
+
+/// A store of verified justifications, keyed by Avail chain id and block number.
+#[async_trait]
+pub trait JustificationStore: Send + Sync {
+    /// Add a justification to the store. Only verified justifications should be added. If a
+    /// justification is already stored for the block, it is kept and the new one is ignored.
+    async fn add_justification(
+        &self,
+        avail_chain_id: &str,
+        justification: GrandpaJustification,
+    ) -> Result<()>;
+
+    /// Record a justification that failed verification, with the reason it was rejected.
+    async fn add_rejected_justification(
+        &self,
+        avail_chain_id: &str,
+        justification: &GrandpaJustification,
+        reason: &str,
+    ) -> Result<()>;
+
+    /// Get the justification for a block. Errors if there is none.
+    async fn get_justification(
+        &self,
+        avail_chain_id: &str,
+        block_number: u32,
+    ) -> Result<GrandpaJustification>;
+
+    /// Check if a justification exists for the given chain and block number.
+    async fn justification_exists(&self, avail_chain_id: &str, block_number: u32) -> Result<bool>;
+
+    /// Get the latest block number with a justification for a given chain.
+    async fn get_latest_block_number(&self, avail_chain_id: &str) -> Result<Option<u32>>;
+
+    /// Get the block numbers with a justification in the range [start_block, end_block] inclusive
+    /// for a given chain, in ascending order.
+    async fn get_justified_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>>;
+
+    /// Get the block numbers without a justification in the range [start_block, end_block]
+    /// inclusive for a given chain, in ascending order.
+    async fn get_missing_block_numbers(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<u32>>;
+
+    /// Record that the Avail node has no justification for the blocks in [start_block, end_block]
+    /// inclusive.
+    async fn add_unjustified_range(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<()>;
+
+    /// Get the recorded ranges without a justification that overlap [start_block, end_block]
+    /// inclusive for a given chain, as inclusive (start_block, end_block) pairs in ascending order.
+    async fn get_unjustified_ranges(
+        &self,
+        avail_chain_id: &str,
+        start_block: u32,
+        end_block: u32,
+    ) -> Result<Vec<(u32, u32)>>;
+
+    /// Convert up to `batch_size` legacy justifications with an id after `after_id`, which are only
+    /// stored as JSON, to the SCALE encoding. Rows are visited in id order.
+    async fn convert_legacy_justifications(
+        &self,
+        after_id: &str,
+        batch_size: usize,
+    ) -> Result<LegacyConversionBatch>;
+
+    /// Remove the justifications for a given chain below `prune_before`, except for the blocks in
+    /// `keep_blocks`. Returns the number of rows removed.
+    async fn prune_justifications(
+        &self,
+        avail_chain_id: &str,
+        prune_before: u32,
+        keep_blocks: &[u32],
+        mode: PruneMode,
+    ) -> Result<u64>;
+
+    /// Add or update an authority set. Fields of the stored set that are None in `authority_set` are
+    /// kept.
+    async fn add_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set: &AuthoritySet,
+    ) -> Result<()>;
+
+    /// Get an authority set by id.
+    async fn get_authority_set(
+        &self,
+        avail_chain_id: &str,
+        authority_set_id: u64,
+    ) -> Result<Option<AuthoritySet>>;
+
+    /// Get the authority set with the highest id.
+    async fn get_latest_authority_set(&self, avail_chain_id: &str) -> Result<Option<AuthoritySet>>;
+
+    /// Get the authority set that attests to the given block. Only sets whose start and epoch end
+    /// blocks are both recorded are matched, so callers fall back to the RPC on `None`.
+    async fn get_authority_set_for_block(
+        &self,
+        avail_chain_id: &str,
+        block_number: u32,
+    ) -> Result<Option<AuthoritySet>>;
+}
+
+/// Encode authorities as their concatenated public keys, for storage.
+pub(crate) fn encode_authorities(authorities: &[B256]) -> Vec<u8> {
+    authorities.iter().flat_map(|a| a.0).collect()
+}
+
+/// Decode authorities stored with [`encode_authorities`].
+pub(crate) fn decode_authorities(bytes: &[u8]) -> Result<Vec<B256>> {
+    if bytes.len() % 32 != 0 {
+        return Err(anyhow::anyhow!(
+            "Invalid encoded authorities length: {}",
+            bytes.len()
+        ));
+    }
+
+    Ok(bytes.chunks_exact(32).map(B256::from_slice).collect())
+}
+
+/// What happens to the justifications removed by pruning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PruneMode {
+    /// Move the rows to the `justifications_archive` table.
+    #[default]
+    Archive,
+    /// Delete the rows.
+    Delete,
+}
+
+impl FromStr for PruneMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "archive" => Self::Archive,
+            "delete" => Self::Delete,
+            _ => return Err(anyhow::anyhow!("Invalid prune mode: {}", s)),
+        })
+    }
+}
+
+/// The result of converting a batch of legacy justifications, stored only as JSON, to the SCALE
+/// encoding.
+#[derive(Debug, Default, Clone)]
+pub struct LegacyConversionBatch {
+    /// The id of the last row in the batch, or None if there were no rows left to convert.
+    pub last_id: Option<String>,
+    /// The number of rows in the batch that were converted.
+    pub converted: usize,
+}
+
+/// Connect to the justification store at the given URL. The backend is selected by the URL scheme:
+/// `postgres://` or `postgresql://` for PostgreSQL, and `sqlite:` for an embedded SQLite file.
+pub async fn connect(database_url: &str) -> Result<Arc<dyn JustificationStore>> {
+    if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        Ok(Arc::new(PostgresClient::connect(database_url).await?))
+    } else if database_url.starts_with("sqlite:") {
+        Ok(Arc::new(SqliteClient::connect(database_url).await?))
+    } else {
+        Err(anyhow::anyhow!(
+            "Unsupported database URL scheme, expected postgres:// or sqlite: : {}",
+            database_url
+        ))
+    }
+}
+
+/// Connect to the justification store at `DATABASE_URL`.
+pub async fn connect_from_env() -> Result<Arc<dyn JustificationStore>> {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+    connect(&database_url).await
+}
+
+/// Convert all of the legacy justifications in the store, which are only stored as JSON, to the SCALE
+/// encoding in batches of `batch_size`. Rows that fail to decode are skipped. Returns the number of
+/// rows converted.
+pub async fn convert_legacy_justifications(
+    store: &dyn JustificationStore,
+    batch_size: usize,
+) -> Result<usize> {
+    let mut after_id = String::new();
+    let mut converted = 0;
+
+    loop {
+        let batch = store
+            .convert_legacy_justifications(&after_id, batch_size)
+            .await?;
+        converted += batch.converted;
+
+        match batch.last_id {
+            Some(last_id) => after_id = last_id,
+            None => return Ok(converted),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use alloy::primitives::B256;
 use avail_subxt::primitives::Header;
 use codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -12,7 +13,7 @@ pub struct Precommit {
     pub target_number: u32,
 }
 
-#[derive(Clone, Debug, Decode, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
 pub struct SignedPrecommit {
     pub precommit: Precommit,
     /// The signature on the message.
@@ -21,7 +22,7 @@ pub struct SignedPrecommit {
     pub id: EdPublic,
 }
 
-#[derive(Clone, Debug, Decode, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
 pub struct Commit {
     pub target_hash: H256,
     /// The target block's number.
@@ -30,7 +31,7 @@ pub struct Commit {
     pub precommits: Vec<SignedPrecommit>,
 }
 
-#[derive(Clone, Debug, Decode, Serialize, Deserialize)]
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
 pub struct GrandpaJustification {
     pub round: u64,
     pub commit: Commit,
@@ -56,3 +57,51 @@ pub struct FinalityProof {
     /// The set of headers in the range (B; F] that are unknown to the caller, ordered by block number.
     pub unknown_headers: Vec<Header>,
 }
+
+/// How an authority set was introduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthoritySetChange {
+    /// A GRANDPA `ScheduledChange` digest.
+    Scheduled,
+    /// A GRANDPA `ForcedChange` digest.
+    Forced,
+}
+
+impl AuthoritySetChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Forced => "forced",
+        }
+    }
+}
+
+impl std::str::FromStr for AuthoritySetChange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "scheduled" => Self::Scheduled,
+            "forced" => Self::Forced,
+            _ => return Err(anyhow::anyhow!("Invalid authority set change: {}", s)),
+        })
+    }
+}
+
+/// A GRANDPA authority set, as recorded by the indexer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthoritySet {
+    pub authority_set_id: u64,
+    /// The public keys of the authorities.
+    pub authorities: Vec<B256>,
+    /// The `compute_authority_set_commitment` hash of the authorities.
+    pub authority_set_hash: B256,
+    /// The epoch end block of the previous authority set, which introduced this set. The set attests
+    /// to the blocks after it.
+    pub start_block: Option<u32>,
+    /// The last block attested to by this set, which introduces the next set. None while the set is
+    /// active.
+    pub epoch_end_block: Option<u32>,
+    /// How the set was introduced, if known.
+    pub change_type: Option<AuthoritySetChange>,
+}
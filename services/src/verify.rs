@@ -0,0 +1,173 @@
+//! Native verification of SP1 Vector proof inputs, so that invalid inputs are rejected before a proof
+//! is requested from the prover network.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use alloy::sol_types::SolType;
+use anyhow::Result;
+use sp1_vector_primitives::header_range::verify_header_range;
+use sp1_vector_primitives::rotate::verify_rotate;
+use sp1_vector_primitives::try_verify_justification;
+use sp1_vector_primitives::types::{
+    CircuitJustification, HeaderRangeInputs, HeaderRangeOutputs, RotateInputs, RotateOutputs,
+};
+
+use crate::input::HeaderRangeRequestData;
+
+/// Run one of the primitives' verifiers, which panic on invalid inputs, and convert a panic into an
+/// error with the panic message.
+fn catch_verification_panic<T>(f: impl FnOnce() -> T) -> Result<T> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        anyhow::anyhow!(message)
+    })
+}
+
+/// Verify a justification is signed by a supermajority of its authority set.
+pub fn check_justification(justification: &CircuitJustification) -> Result<()> {
+    try_verify_justification(justification).map_err(|e| {
+        anyhow::anyhow!(
+            "Justification for block hash {} from authority set {} is invalid: {}",
+            justification.block_hash,
+            justification.authority_set_id,
+            e
+        )
+    })
+}
+
+/// Run the same checks as the SP1 Vector program on the header range inputs, which include the
+/// verification of the target justification, and confirm the outputs match the requested header
+/// range.
+pub fn check_header_range_inputs(
+    header_range_inputs: &HeaderRangeInputs,
+    header_range_request_data: HeaderRangeRequestData,
+) -> Result<()> {
+    let trusted_block = header_range_request_data.trusted_block;
+    let target_block = header_range_request_data.target_block;
+
+    let expected_num_headers = (target_block - trusted_block + 1) as usize;
+    if header_range_inputs.encoded_headers.len() != expected_num_headers {
+        return Err(anyhow::anyhow!(
+            "Header range inputs from block {} to block {} have {} headers, expected {}",
+            trusted_block,
+            target_block,
+            header_range_inputs.encoded_headers.len(),
+            expected_num_headers
+        ));
+    }
+
+    let outputs = catch_verification_panic(|| verify_header_range(header_range_inputs.clone()))
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Header range inputs from block {} to block {} failed verification: {}",
+                trusted_block,
+                target_block,
+                e
+            )
+        })?;
+
+    let (output_trusted_block, _, _, _, output_target_block, _, _, _, _) =
+        HeaderRangeOutputs::abi_decode(&outputs)?;
+    if output_trusted_block != trusted_block || output_target_block != target_block {
+        return Err(anyhow::anyhow!(
+            "Header range inputs prove blocks {} to {}, expected {} to {}",
+            output_trusted_block,
+            output_target_block,
+            trusted_block,
+            target_block
+        ));
+    }
+
+    Ok(())
+}
+
+/// Run the same checks as the SP1 Vector program on the rotate inputs, which include the verification
+/// of the justification, and confirm the justification is from the expected authority set.
+pub fn check_rotate_inputs(rotate_inputs: &RotateInputs, authority_set_id: u64) -> Result<()> {
+    let outputs =
+        catch_verification_panic(|| verify_rotate(rotate_inputs.clone())).map_err(|e| {
+            anyhow::anyhow!(
+                "Rotate inputs for authority set {} failed verification: {}",
+                authority_set_id,
+                e
+            )
+        })?;
+
+    let (output_authority_set_id, _, _) = RotateOutputs::abi_decode(&outputs)?;
+    if output_authority_set_id != authority_set_id {
+        return Err(anyhow::anyhow!(
+            "Rotate inputs are justified by authority set {}, expected {}",
+            output_authority_set_id,
+            authority_set_id
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use alloy::primitives::B256;
+    use avail_subxt::primitives::Header;
+    use serde::Deserialize;
+    use sp_core::ed25519::Public;
+
+    use super::*;
+    use crate::input::convert_justification_and_valset_to_circuit;
+    use crate::types::{Commit, GrandpaJustification};
+
+    #[derive(Deserialize)]
+    struct ValidatorSet {
+        set_id: u64,
+        validator_set: Vec<Public>,
+    }
+
+    #[derive(Deserialize)]
+    struct Justification {
+        round: u64,
+        commit: Commit,
+        votes_ancestries: Vec<Header>,
+    }
+
+    #[derive(Deserialize)]
+    struct ValidatorSetAndJustification {
+        validator_set: ValidatorSet,
+        justification: Justification,
+    }
+
+    fn load_circuit_justification(path: &str) -> CircuitJustification {
+        let test_case: ValidatorSetAndJustification =
+            serde_json::from_reader(File::open(path).unwrap()).unwrap();
+
+        convert_justification_and_valset_to_circuit(
+            GrandpaJustification {
+                round: test_case.justification.round,
+                commit: test_case.justification.commit,
+                votes_ancestries: test_case.justification.votes_ancestries,
+            },
+            test_case
+                .validator_set
+                .validator_set
+                .iter()
+                .map(|e| B256::from(e.0))
+                .collect(),
+            test_case.validator_set.set_id,
+        )
+    }
+
+    #[test]
+    fn test_check_justification_returns_error() {
+        let valid = load_circuit_justification("test_assets/ancestry.json");
+        assert!(check_justification(&valid).is_ok());
+
+        let no_majority =
+            load_circuit_justification("test_assets/ancestry_missing_link_no_majority.json");
+        assert!(check_justification(&no_majority).is_err());
+    }
+}